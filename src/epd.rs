@@ -0,0 +1,260 @@
+use crate::position::{FenError, Position};
+use crate::san::SanError;
+use crate::search::{self, SearchLimits, SearchOptions};
+use crate::transposition_table::TranspositionTable;
+use crate::utils::Move;
+use std::fmt;
+use std::io::BufRead;
+use std::time::Instant;
+
+// https://www.chessprogramming.org/Extended_Position_Description
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EpdError {
+    MissingField(&'static str),
+    InvalidFen(FenError),
+    UnterminatedString,
+    InvalidMove(SanError),
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::MissingField(field) => write!(f, "Missing {} field", field),
+            EpdError::InvalidFen(error) => write!(f, "{}", error),
+            EpdError::UnterminatedString => write!(f, "Unterminated string operand"),
+            EpdError::InvalidMove(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+// The four fields of a FEN without the move counters, followed by operations: opcode operands;
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpdRecord {
+    position: Position,
+    operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    pub fn parse(line: &str) -> Result<EpdRecord, EpdError> {
+        let mut rest = line.trim();
+        let mut fields = vec![];
+        for name in ["board", "turn", "castling", "en passant"] {
+            let (field, remaining) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                return Err(EpdError::MissingField(name));
+            }
+            fields.push(field);
+            rest = remaining.trim_start();
+        }
+        let operations = parse_operations(rest)?;
+
+        // The hmvc and fmvn operations stand for the move counters
+        let counter = |opcode: &str, default: &'static str| {
+            operations
+                .iter()
+                .find(|(name, _)| name == opcode)
+                .and_then(|(_, operands)| operands.first().cloned())
+                .unwrap_or(default.to_string())
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            counter("hmvc", "0"),
+            counter("fmvn", "1")
+        );
+        let position = Position::try_from_fen(&fen).map_err(EpdError::InvalidFen)?;
+        Ok(EpdRecord {
+            position,
+            operations,
+        })
+    }
+
+    pub fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn get_operations(&self) -> &[(String, Vec<String>)] {
+        &self.operations
+    }
+
+    pub fn get_operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        self.get_operation("id")
+            .and_then(|operands| operands.first())
+            .map(|id| id.as_str())
+    }
+
+    pub fn get_comment(&self) -> Option<&str> {
+        self.get_operation("c0")
+            .and_then(|operands| operands.first())
+            .map(|comment| comment.as_str())
+    }
+
+    pub fn get_best_moves(&self) -> Result<Vec<Move>, EpdError> {
+        self.get_moves("bm")
+    }
+
+    pub fn get_avoid_moves(&self) -> Result<Vec<Move>, EpdError> {
+        self.get_moves("am")
+    }
+
+    // The moves of the operation are written in SAN
+    fn get_moves(&self, opcode: &str) -> Result<Vec<Move>, EpdError> {
+        self.get_operation(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| self.position.parse_san(san).map_err(EpdError::InvalidMove))
+            .collect()
+    }
+
+    // The move found has to be one of the best moves and none of the moves to avoid
+    pub fn is_solved_by(&self, mov: &Move) -> Result<bool, EpdError> {
+        let best_moves = self.get_best_moves()?;
+        let avoid_moves = self.get_avoid_moves()?;
+        Ok(
+            (best_moves.is_empty() || best_moves.iter().any(|m| m.to_u16() == mov.to_u16()))
+                && avoid_moves.iter().all(|m| m.to_u16() != mov.to_u16()),
+        )
+    }
+}
+
+// Operands are separated by spaces, a string operand is quoted and may contain spaces and semicolons
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = vec![];
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(operations);
+        }
+
+        let mut opcode = String::new();
+        while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != ';') {
+            opcode.push(ch);
+        }
+        let mut operands = vec![];
+        loop {
+            while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+            match chars.next() {
+                // The last semicolon is often left out
+                None | Some(';') => break,
+                Some('"') => {
+                    let mut operand = String::new();
+                    loop {
+                        match chars.next() {
+                            None => return Err(EpdError::UnterminatedString),
+                            Some('"') => break,
+                            Some(ch) => operand.push(ch),
+                        }
+                    }
+                    operands.push(operand);
+                }
+                Some(ch) => {
+                    let mut operand = ch.to_string();
+                    while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != ';') {
+                        operand.push(ch);
+                    }
+                    operands.push(operand);
+                }
+            }
+        }
+        if !opcode.is_empty() {
+            operations.push((opcode, operands));
+        }
+    }
+}
+
+// Searches every position of the suite and prints a line for each, then the score of the engine
+// Returns the number of solved positions and the number of positions with bm or am operations
+pub fn run_suite(
+    reader: impl BufRead,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    transposition_table: &TranspositionTable,
+) -> (usize, usize) {
+    let start = Instant::now();
+    let mut solved = 0;
+    let mut total = 0;
+    let mut nodes = 0;
+
+    for (index, line) in reader.lines().map_while(Result::ok).enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let record = match EpdRecord::parse(line) {
+            Ok(record) => record,
+            Err(error) => {
+                println!("line {}: {}", index + 1, error);
+                continue;
+            }
+        };
+        let id = record
+            .get_id()
+            .map_or_else(|| format!("line {}", index + 1), |id| id.to_string());
+        let expected = match (record.get_operation("bm"), record.get_operation("am")) {
+            (Some(best_moves), _) => format!("bm {}", best_moves.join(" ")),
+            (None, Some(avoid_moves)) => format!("am {}", avoid_moves.join(" ")),
+            (None, None) => {
+                println!("{}: no bm or am operation", id);
+                continue;
+            }
+        };
+
+        transposition_table.clear();
+        let mut position = record.get_position().clone();
+        let position_start = Instant::now();
+        let result = search::analyse(&mut position, limits, transposition_table, options);
+        nodes += result.nodes;
+
+        let (found, is_solved) = match result.best_move {
+            Some(mov) => (
+                record.get_position().move_to_san(&mov),
+                record.is_solved_by(&mov),
+            ),
+            None => ("none".to_string(), Ok(false)),
+        };
+        let status = match is_solved {
+            Ok(true) => "solved",
+            Ok(false) => "failed",
+            Err(error) => {
+                println!("{}: {}", id, error);
+                continue;
+            }
+        };
+        total += 1;
+        if status == "solved" {
+            solved += 1;
+        }
+        println!(
+            "{}: {} {} ({}) score {} depth {} nodes {} time {}",
+            id,
+            status,
+            found,
+            expected,
+            result.score,
+            result.depth,
+            result.nodes,
+            position_start.elapsed().as_millis()
+        );
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "Solved {}/{} ({:.1}%) nodes {} time {} nps {}",
+        solved,
+        total,
+        100.0 * solved as f64 / total.max(1) as f64,
+        nodes,
+        elapsed.as_millis(),
+        (nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64
+    );
+    (solved, total)
+}
@@ -1,11 +1,18 @@
 use crate::position::Position;
-use crate::utils::{Piece, PieceColor, PieceType, count_set_bit};
+use crate::utils::{Move, Piece, PieceColor, PieceType, count_set_bit};
 
+// Score from the point of view of White
 #[inline(always)]
 pub fn evaluate(position: &Position) -> i32 {
     pst_evaluation(position)
 }
 
+// Score from the point of view of the side to move, as needed by negamax
+#[inline(always)]
+pub fn evaluate_relative(position: &Position) -> i32 {
+    evaluate(position) * position.get_turn() as i32
+}
+
 #[inline(always)]
 fn pst_evaluation(position: &Position) -> i32 {
     let mut score = 0;
@@ -47,62 +54,62 @@ fn pst_evaluation(position: &Position) -> i32 {
 pub fn get_pst_value(piece: &Piece, index: &i8) -> i32 {
     #[rustfmt::skip]
     let pawn_table = vec![
-        00,  00,  00,  00,  00,  00,  00,  00,
+         0,   0,   0,   0,   0,   0,   0,   0,
         50,  50,  50,  50,  50,  50,  50,  50,
         10,  10,  20,  30,  30,  20,  10,  10,
-        05,  05,  10,  25,  25,  10,  05,  05,
-        00,  00,  00,  20,  20,  00,  00,  00,
-        05, -05, -10,  00,  00, -10, -05,  05,
-        05,  10,  10, -20, -20,  10,  10,  05,
-        00,  00,  00,  00,  00,  00,  00,  00
+         5,   5,  10,  25,  25,  10,   5,   5,
+         0,   0,   0,  20,  20,   0,   0,   0,
+         5,  -5, -10,   0,   0, -10,  -5,   5,
+         5,  10,  10, -20, -20,  10,  10,   5,
+         0,   0,   0,   0,   0,   0,   0,   0
     ];
 
     #[rustfmt::skip]
     let knight_table = vec![
         -50, -40, -30, -30, -30, -30, -40, -50,
-        -40, -20,  00,  00,  00,  00, -20, -40,
-        -30,  00,  10,  15,  15,  10,  00, -30,
-        -30,  05,  15,  20,  20,  15,  05, -30,
-        -30,  00,  15,  20,  20,  15,  00, -30,
-        -30,  05,  10,  15,  15,  10,  05, -30,
-        -40, -20,  00,  05,  05,  00, -20, -40,
+        -40, -20,   0,   0,   0,   0, -20, -40,
+        -30,   0,  10,  15,  15,  10,   0, -30,
+        -30,   5,  15,  20,  20,  15,   5, -30,
+        -30,   0,  15,  20,  20,  15,   0, -30,
+        -30,   5,  10,  15,  15,  10,   5, -30,
+        -40, -20,   0,   5,   5,   0, -20, -40,
         -50, -40, -30, -30, -30, -30, -40, -50,
     ];
 
     #[rustfmt::skip]
     let bishop_table = vec![
         -20, -10, -10, -10, -10, -10, -10, -20,
-        -10,  00,  00,  00,  00,  00,  00, -10,
-        -10,  00,  05,  10,  10,  05,  00, -10,
-        -10,  05,  05,  10,  10,  05,  05, -10,
-        -10,  00,  10,  10,  10,  10,  00, -10,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,  10,  10,   5,   0, -10,
+        -10,   5,   5,  10,  10,   5,   5, -10,
+        -10,   0,  10,  10,  10,  10,   0, -10,
         -10,  10,  10,  10,  10,  10,  10, -10,
-        -10,  05,  00,  00,  00,  00,  05, -10,
+        -10,   5,   0,   0,   0,   0,   5, -10,
         -20, -10, -10, -10, -10, -10, -10, -20,
     ];
 
     #[rustfmt::skip]
     let rook_table = vec![
-         00,  00,  00,  00,  00,  00,  00,  00,
-         05,  10,  10,  10,  10,  10,  10,  05,
-        -05,  00,  00,  00,  00,  00,  00, -05,
-        -05,  00,  00,  00,  00,  00,  00, -05,
-        -05,  00,  00,  00,  00,  00,  00, -05,
-        -05,  00,  00,  00,  00,  00,  00, -05,
-        -05,  00,  00,  00,  00,  00,  00, -05,
-         00,  00,  00,  50,  50,  00,  00,  00
+          0,   0,   0,   0,   0,   0,   0,   0,
+          5,  10,  10,  10,  10,  10,  10,   5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+          0,   0,   0,  50,  50,   0,   0,   0
     ];
 
     #[rustfmt::skip]
     let queen_table = vec![
-        -20, -10, -10, -05, -05, -10, -10, -20,
-        -10,  00,  00,  00,  00,  00,  00, -10,
-        -10,  00,  05,  05,  05,  05,  00, -10,
-        -05,  00,  05,  05,  05,  05,  00, -05,
-         00,  00,  05,  05,  05,  05,  00, -05,
-        -10,  05,  05,  05,  05,  05,  00, -10,
-        -10,  00,  05,  00,  00,  00,  00, -10,
-        -20, -10, -10, -05, -05, -10, -10, -20
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+          0,   0,   5,   5,   5,   5,   0,  -5,
+        -10,   5,   5,   5,   5,   5,   0, -10,
+        -10,   0,   5,   0,   0,   0,   0, -10,
+        -20, -10, -10,  -5,  -5, -10, -10, -20
     ];
 
     #[rustfmt::skip]
@@ -113,8 +120,8 @@ pub fn get_pst_value(piece: &Piece, index: &i8) -> i32 {
         -30, -40, -40, -50, -50, -40, -40, -30,
         -20, -30, -30, -40, -40, -30, -30, -20,
         -10, -20, -20, -20, -20, -20, -20, -10,
-         20,  20,  00,  00,  00,  00,  20,  20,
-         20,  30,  10,  00,  00,  10,  30,  20
+         20,  20,   0,   0,   0,   0,  20,  20,
+         20,  30,  10,   0,   0,  10,  30,  20
     ];
 
     let mut rank = index / 8;
@@ -135,10 +142,11 @@ pub fn get_pst_value(piece: &Piece, index: &i8) -> i32 {
     value as i32 * (piece.color as i16) as i32
 }
 
+// MVV-LVA: the most valuable victim first and, for the same victim, the least valuable attacker
 #[inline(always)]
-pub fn evaluate_move(position: &Position, source: &i8, destination: &i8) -> i32 {
-    let source_piece = position.get_piece_on_square(source);
-    let destination_piece = position.get_piece_on_square(&destination);
+pub fn evaluate_move(position: &Position, mov: &Move) -> i32 {
+    let source_piece = position.get_piece_on_square(&mov.source);
+    let captured_piece = position.get_captured_piece(mov);
     let mut move_score: i32 = 0;
 
     move_score += match source_piece.piece_type {
@@ -151,14 +159,22 @@ pub fn evaluate_move(position: &Position, source: &i8, destination: &i8) -> i32
         PieceType::King => -100,
     };
 
-    move_score += match destination_piece.piece_type {
-        PieceType::None => -50,
-        PieceType::Pawn => 10,
-        PieceType::Knight => 30,
-        PieceType::Bishop => 30,
-        PieceType::Rook => 50,
-        PieceType::Queen => 90,
-        PieceType::King => 100,
+    move_score += 10
+        * match captured_piece {
+            PieceType::None => -50,
+            PieceType::Pawn => 10,
+            PieceType::Knight => 30,
+            PieceType::Bishop => 30,
+            PieceType::Rook => 50,
+            PieceType::Queen => 90,
+            PieceType::King => 100,
+        };
+
+    // A promotion is worth as much as capturing the new piece
+    move_score += match mov.move_type.promotion_piece() {
+        Some(PieceType::Queen) => 800,
+        Some(_) => 100,
+        None => 0,
     };
 
     move_score
@@ -170,7 +186,7 @@ pub fn simple_evaluation(position: &Position) -> i32 {
     let mut board = position.get_board();
     while board != 0 {
         let piece = position.get_piece_on_square(&(board.trailing_zeros() as i8));
-        score = score + (piece.piece_type as i16 * piece.color as i16) as i32;
+        score += (piece.piece_type as i16 * piece.color as i16) as i32;
         board &= board - 1;
     }
     score
@@ -1,8 +1,16 @@
+pub mod epd;
 pub mod evaluation;
 pub mod lookup_tables;
+pub mod move_list;
+pub mod move_picker;
 pub mod moves_generator;
+pub mod options;
+pub mod pgn;
 pub mod position;
+pub mod san;
 pub mod search;
+pub mod time_manager;
+pub mod transposition_table;
 pub mod uci;
 pub mod utils;
 pub mod zobrist_hash;
@@ -2,7 +2,10 @@ use crate::utils::random_u64_few_bits;
 use std::collections::HashSet;
 use std::sync::LazyLock;
 
-pub static LOOK_UP_TABLE: LazyLock<LookUpTable> = LazyLock::new(|| LookUpTable::init());
+pub static LOOK_UP_TABLE: LazyLock<LookUpTable> = LazyLock::new(LookUpTable::init);
+
+// A mask for every pair of squares
+pub type SquarePairMasks = Box<[[u64; 64]; 64]>;
 
 pub struct LookUpTable {
     pub knight_attacks: [u64; 64],
@@ -15,6 +18,8 @@ pub struct LookUpTable {
     pub bishop_blockers_masks: [u64; 64],
     pub bishop_magics: [u64; 64],
     pub bishop_attacks: Box<[[u64; 512]; 64]>,
+    pub between: SquarePairMasks,
+    pub line: SquarePairMasks,
 }
 
 impl LookUpTable {
@@ -23,6 +28,7 @@ impl LookUpTable {
         let bishop_magics_and_attacks = generate_bishop_attacks();
         let bishop_anti_diag_blockers_masks = generate_bishop_anti_diag_blockers_masks();
         let bishop_diag_blockers_masks = generate_bishop_diag_blockers_masks();
+        let (between, line) = generate_between_and_line();
 
         let mut bishop_blockers_masks = [0u64; 64];
         for i in 0..64 {
@@ -43,13 +49,17 @@ impl LookUpTable {
             bishop_blockers_masks,
             bishop_magics: bishop_magics_and_attacks.0,
             bishop_attacks: bishop_magics_and_attacks.1,
+
+            between,
+            line,
         }
     }
 }
 
 pub fn generate_rook_attacks() -> ([u64; 64], Box<[[u64; 4096]; 64]>) {
     let mut magic_numbers = [0u64; 64];
-    let mut attacks = Box::new([[0u64; 4096]; 64]);
+    // Built on the heap, the table is too large for the stack of a debug build
+    let mut attacks: Box<[[u64; 4096]; 64]> = vec![[0u64; 4096]; 64].try_into().unwrap();
 
     for square in 0..64 {
         let occupancy_combos = generate_rook_occupancy_combos(&square);
@@ -84,7 +94,7 @@ pub fn generate_rook_attacks() -> ([u64; 64], Box<[[u64; 4096]; 64]>) {
 
 pub fn generate_bishop_attacks() -> ([u64; 64], Box<[[u64; 512]; 64]>) {
     let mut magic_numbers = [0u64; 64];
-    let mut attacks = Box::new([[0u64; 512]; 64]);
+    let mut attacks: Box<[[u64; 512]; 64]> = vec![[0u64; 512]; 64].try_into().unwrap();
 
     for square in 0..64 {
         let occupancy_combos = generate_bishop_occupancy_combos(&square);
@@ -155,11 +165,10 @@ pub fn generate_bishop_occupancy_combos(square: &u8) -> HashSet<(u64, u64)> {
     let mut final_anti_diag_occupancy = [(0, 0); 64];
     let anti_diag_blockers_masks = generate_bishop_anti_diag_blockers_masks()[*square as usize];
     let anti_diag_masks = generate_bishop_anti_diag_masks()[*square as usize];
-    let mut i = 0;
-    for (mut mask, mut moves) in anti_diag_occupancy {
+    for (i, (mut mask, mut moves)) in anti_diag_occupancy.into_iter().enumerate() {
         if distance_to_anti_diag < 0 {
-            mask >>= -1 * distance_to_anti_diag; // Move to the left;
-            moves >>= -1 * distance_to_anti_diag; // Move to the left;
+            mask >>= -distance_to_anti_diag; // Move to the left;
+            moves >>= -distance_to_anti_diag; // Move to the left;
         } else if distance_to_anti_diag > 0 {
             mask <<= distance_to_anti_diag; // Move to the right
             moves <<= distance_to_anti_diag; // Move to the right
@@ -167,7 +176,6 @@ pub fn generate_bishop_occupancy_combos(square: &u8) -> HashSet<(u64, u64)> {
         mask &= anti_diag_blockers_masks;
         moves &= anti_diag_masks;
         final_anti_diag_occupancy[i] = (mask, moves);
-        i += 1;
     }
 
     let distance_to_diag: i8 = file as i8 - reference[(7 - rank) as usize] as i8;
@@ -176,11 +184,10 @@ pub fn generate_bishop_occupancy_combos(square: &u8) -> HashSet<(u64, u64)> {
     let mut final_diag_occupancy = [(0, 0); 64];
     let diag_blockers_masks = generate_bishop_diag_blockers_masks()[*square as usize];
     let diag_masks = generate_bishop_diag_masks()[*square as usize];
-    let mut i = 0;
-    for (mut mask, mut moves) in diag_occupancy {
+    for (i, (mut mask, mut moves)) in diag_occupancy.into_iter().enumerate() {
         if distance_to_diag < 0 {
-            mask >>= -1 * distance_to_diag; // Move to the left
-            moves >>= -1 * distance_to_diag; // Move to the left
+            mask >>= -distance_to_diag; // Move to the left
+            moves >>= -distance_to_diag; // Move to the left
         } else if distance_to_diag > 0 {
             mask <<= distance_to_diag; // Move to the right
             moves <<= distance_to_diag; // Move to the right
@@ -188,7 +195,6 @@ pub fn generate_bishop_occupancy_combos(square: &u8) -> HashSet<(u64, u64)> {
         mask &= diag_blockers_masks;
         moves &= diag_masks;
         final_diag_occupancy[i] = (mask, moves);
-        i += 1;
     }
 
     let mut hash_table: HashSet<(u64, u64)> = HashSet::with_capacity(512);
@@ -211,7 +217,7 @@ pub fn generate_rook_rank_1_occupancy(square: &u8) -> [(u64, u64); 64] {
     let mut masks_list = [0u64; 64];
     let mut j = 0;
     for i in 0..=127 {
-        if (i >> index) & 1 != 0 || (i >> 0) & 1 != 0 {
+        if (i >> index) & 1 != 0 || i & 1 != 0 {
             continue;
         }
         masks_list[j] = i as u64;
@@ -256,9 +262,8 @@ pub fn generate_bishop_anti_diag_occupancy(square: &u8) -> [(u64, u64); 64] {
     let temp_occupancy = generate_rook_rank_1_occupancy(square);
     let mut occupancy: [(u64, u64); 64] = [(0, 0); 64];
 
-    let mut j = 0;
-    for (mask, moves) in temp_occupancy {
-        let final_mask = ((mask >> 0 & 1) << 0)
+    for (j, (mask, moves)) in temp_occupancy.into_iter().enumerate() {
+        let final_mask = (mask & 1)
             | ((mask >> 1 & 1) << 9)
             | ((mask >> 2 & 1) << 18)
             | ((mask >> 3 & 1) << 27)
@@ -267,7 +272,7 @@ pub fn generate_bishop_anti_diag_occupancy(square: &u8) -> [(u64, u64); 64] {
             | ((mask >> 6 & 1) << 54)
             | ((mask >> 7 & 1) << 63);
 
-        let final_moves = ((moves >> 0 & 1) << 0)
+        let final_moves = (moves & 1)
             | ((moves >> 1 & 1) << 9)
             | ((moves >> 2 & 1) << 18)
             | ((moves >> 3 & 1) << 27)
@@ -277,7 +282,6 @@ pub fn generate_bishop_anti_diag_occupancy(square: &u8) -> [(u64, u64); 64] {
             | ((moves >> 7 & 1) << 63);
 
         occupancy[j] = (final_mask, final_moves);
-        j += 1;
     }
     occupancy
 }
@@ -286,9 +290,8 @@ pub fn generate_bishop_diag_occupancy(square: &u8) -> [(u64, u64); 64] {
     let temp_occupancy = generate_rook_rank_1_occupancy(square);
     let mut occupancy: [(u64, u64); 64] = [(0, 0); 64];
 
-    let mut j = 0;
-    for (mask, moves) in temp_occupancy {
-        let final_mask = ((mask >> 0 & 1) << 56)
+    for (j, (mask, moves)) in temp_occupancy.into_iter().enumerate() {
+        let final_mask = ((mask & 1) << 56)
             | ((mask >> 1 & 1) << 49)
             | ((mask >> 2 & 1) << 42)
             | ((mask >> 3 & 1) << 35)
@@ -297,7 +300,7 @@ pub fn generate_bishop_diag_occupancy(square: &u8) -> [(u64, u64); 64] {
             | ((mask >> 6 & 1) << 14)
             | ((mask >> 7 & 1) << 7);
 
-        let final_moves = ((moves >> 0 & 1) << 56)
+        let final_moves = ((moves & 1) << 56)
             | ((moves >> 1 & 1) << 49)
             | ((moves >> 2 & 1) << 42)
             | ((moves >> 3 & 1) << 35)
@@ -307,13 +310,12 @@ pub fn generate_bishop_diag_occupancy(square: &u8) -> [(u64, u64); 64] {
             | ((moves >> 7 & 1) << 7);
 
         occupancy[j] = (final_mask, final_moves);
-        j += 1;
     }
     occupancy
 }
 
 pub fn rank_to_file(rank: &u64) -> u64 {
-    ((rank >> 0) & 1u64) << 0
+    (*rank & 1u64)
         | ((rank >> 1) & 1u64) << 8
         | ((rank >> 2) & 1u64) << 16
         | ((rank >> 3) & 1u64) << 24
@@ -323,6 +325,49 @@ pub fn rank_to_file(rank: &u64) -> u64 {
         | ((rank >> 7) & 1u64) << 56
 }
 
+// For two squares on the same rank, file or diagonal, the squares strictly between them
+// and the whole line going through both, empty otherwise
+pub fn generate_between_and_line() -> (SquarePairMasks, SquarePairMasks) {
+    let mut between: SquarePairMasks = vec![[0u64; 64]; 64].try_into().unwrap();
+    let mut line: SquarePairMasks = vec![[0u64; 64]; 64].try_into().unwrap();
+
+    for square in 0..64i8 {
+        for (rank_step, file_step) in [
+            (0, 1),
+            (1, 1),
+            (1, 0),
+            (1, -1),
+            (0, -1),
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+        ] {
+            // The whole line is the ray in this direction and the one in the opposite direction
+            let ray = |rank_step: i8, file_step: i8| {
+                let mut squares = vec![];
+                let (mut rank, mut file) = (square / 8 + rank_step, square % 8 + file_step);
+                while (0..8).contains(&rank) && (0..8).contains(&file) {
+                    squares.push(rank * 8 + file);
+                    rank += rank_step;
+                    file += file_step;
+                }
+                squares
+            };
+            let forward = ray(rank_step, file_step);
+            let full_line = (forward.iter().chain(ray(-rank_step, -file_step).iter()))
+                .fold(1u64 << square, |mask, target| mask | 1u64 << target);
+
+            let mut squares_between = 0u64;
+            for target in forward {
+                between[square as usize][target as usize] = squares_between;
+                line[square as usize][target as usize] = full_line;
+                squares_between |= 1u64 << target;
+            }
+        }
+    }
+    (between, line)
+}
+
 pub fn generate_rook_blockers_masks() -> [u64; 64] {
     let mut masks = [0u64; 64];
 
@@ -346,7 +391,7 @@ pub fn generate_bishop_anti_diag_blockers_masks() -> [u64; 64] {
     let mut masks = [0u64; 64];
 
     let mut anti_diag_mask_list: [u64; 8] = [0u64; 8];
-    for i in 0..8 {
+    for (i, anti_diag_mask_entry) in anti_diag_mask_list.iter_mut().enumerate() {
         let mut anti_diag_mask: u64 = 0;
         let mut bit_index = 56 - 8 * i;
         while bit_index < 64 {
@@ -355,7 +400,7 @@ pub fn generate_bishop_anti_diag_blockers_masks() -> [u64; 64] {
         }
         anti_diag_mask &= !(1u64 << (56 - 8 * i));
         anti_diag_mask &= !(1u64 << (bit_index - 9));
-        anti_diag_mask_list[i] = anti_diag_mask;
+        *anti_diag_mask_entry = anti_diag_mask;
     }
 
     let anti_diagonal_reference = [0, 1, 2, 3, 4, 5, 6, 7];
@@ -426,14 +471,14 @@ pub fn generate_bishop_anti_diag_masks() -> [u64; 64] {
     let mut masks = [0u64; 64];
 
     let mut anti_diag_mask_list: [u64; 8] = [0u64; 8];
-    for i in 0..8 {
+    for (i, anti_diag_mask_entry) in anti_diag_mask_list.iter_mut().enumerate() {
         let mut anti_diag_mask: u64 = 0;
         let mut bit_index = 56 - 8 * i;
         while bit_index < 64 {
             anti_diag_mask |= 1u64 << bit_index;
             bit_index += 9;
         }
-        anti_diag_mask_list[i] = anti_diag_mask;
+        *anti_diag_mask_entry = anti_diag_mask;
     }
 
     let anti_diagonal_reference = [0, 1, 2, 3, 4, 5, 6, 7];
@@ -502,28 +547,28 @@ pub fn generate_knight_mask() -> [u64; 64] {
     for rank in 1..=8 {
         for file in 'a'..='h' {
             let r = rank - 1;
-            let f = (file as u8 - 'a' as u8) as i8;
-            let index = r * 8 + f as i8;
+            let f = (file as u8 - b'a') as i8;
+            let index = r * 8 + f;
             let mut knight_mask: u64 = 0;
 
             if 0 <= f - 2 {
-                if 0 <= r - 1 {
+                if 0 < r {
                     knight_mask |= 1u64 << ((r - 1) * 8 + f - 2);
                 }
-                if r + 1 <= 7 {
+                if r < 7 {
                     knight_mask |= 1u64 << ((r + 1) * 8 + f - 2);
                 }
             }
             if f + 2 <= 7 {
-                if 0 <= r - 1 {
+                if 0 < r {
                     knight_mask |= 1u64 << ((r - 1) * 8 + f + 2);
                 }
-                if r + 1 <= 7 {
+                if r < 7 {
                     knight_mask |= 1u64 << ((r + 1) * 8 + f + 2);
                 }
             }
 
-            if 0 <= f - 1 {
+            if 0 < f {
                 if 0 <= r - 2 {
                     knight_mask |= 1u64 << ((r - 2) * 8 + f - 1);
                 }
@@ -531,7 +576,7 @@ pub fn generate_knight_mask() -> [u64; 64] {
                     knight_mask |= 1u64 << ((r + 2) * 8 + f - 1);
                 }
             }
-            if f + 1 <= 7 {
+            if f < 7 {
                 if 0 <= r - 2 {
                     knight_mask |= 1u64 << ((r - 2) * 8 + f + 1);
                 }
@@ -552,33 +597,33 @@ pub fn generate_king_mask() -> [u64; 64] {
     for rank in 1..=8 {
         for file in 'a'..='h' {
             let r = rank - 1;
-            let f = (file as u8 - 'a' as u8) as i8;
-            let index = r * 8 + f as i8;
+            let f = (file as u8 - b'a') as i8;
+            let index = r * 8 + f;
             let mut king_mask: u64 = 0;
 
-            if 0 <= f - 1 {
+            if 0 < f {
                 king_mask |= 1u64 << (r * 8 + f - 1);
-                if 0 <= r - 1 {
+                if 0 < r {
                     king_mask |= 1u64 << ((r - 1) * 8 + f - 1);
                 }
-                if r + 1 <= 7 {
+                if r < 7 {
                     king_mask |= 1u64 << ((r + 1) * 8 + f - 1);
                 }
             }
-            if f + 1 <= 7 {
+            if f < 7 {
                 king_mask |= 1u64 << (r * 8 + f + 1);
-                if 0 <= r - 1 {
+                if 0 < r {
                     king_mask |= 1u64 << ((r - 1) * 8 + f + 1);
                 }
-                if r + 1 <= 7 {
+                if r < 7 {
                     king_mask |= 1u64 << ((r + 1) * 8 + f + 1);
                 }
             }
 
-            if 0 <= r - 1 {
+            if 0 < r {
                 king_mask |= 1u64 << ((r - 1) * 8 + f);
             }
-            if r + 1 <= 7 {
+            if r < 7 {
                 king_mask |= 1u64 << ((r + 1) * 8 + f);
             }
             king_mask_list[index as usize] = king_mask;
@@ -594,16 +639,16 @@ pub fn generate_white_pawn_attacks() -> [u64; 64] {
     for rank in 1..=8 {
         for file in 'a'..='h' {
             let r = rank - 1;
-            let f = (file as u8 - 'a' as u8) as i8;
-            let index = r * 8 + f as i8;
+            let f = (file as u8 - b'a') as i8;
+            let index = r * 8 + f;
             let mut mask: u64 = 0;
             if rank < 8 {
                 // No upper left for the file a
-                if 0 <= f - 1 {
+                if 0 < f {
                     mask |= 1 << (index + 7);
                 }
                 // No upper right for the file h
-                if f + 1 <= 7 {
+                if f < 7 {
                     mask |= 1 << (index + 9);
                 }
             }
@@ -619,16 +664,16 @@ pub fn generate_black_pawn_attacks() -> [u64; 64] {
     for rank in 1..=8 {
         for file in 'a'..='h' {
             let r = rank - 1;
-            let f = (file as u8 - 'a' as u8) as i8;
-            let index = r * 8 + f as i8;
+            let f = (file as u8 - b'a') as i8;
+            let index = r * 8 + f;
             let mut mask: u64 = 0;
             if rank > 1 {
                 // No lower right for the file 'h'
-                if f + 1 <= 7 {
+                if f < 7 {
                     mask |= 1 << (index - 7);
                 }
                 // No lower left for the file a
-                if 0 <= f - 1 {
+                if 0 < f {
                     mask |= 1 << (index - 9);
                 }
             }
@@ -10,22 +10,83 @@
 // https://markus7800.github.io/blog/AI/chess_engine.html
 // https://joeyrobert.org/2016/01/06/optimizing-move-generation/
 
+use std::fs::File;
+use std::io::BufReader;
+use std::thread;
 use std::time::Instant;
+use zeno::epd;
 use zeno::perft;
 use zeno::position::Position;
+use zeno::search::{SEARCH_THREAD_STACK_SIZE, SearchLimits, SearchOptions};
+use zeno::transposition_table::{DEFAULT_HASH_SIZE_MB, TranspositionTable};
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("perft") => run_perft(),
+        Some("epd") => run_epd(&args[2..]),
+        _ => zeno::uci::uci_loop(),
+    }
+}
+
+fn run_perft() {
     let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
-    let p = Position::from_fen(fen);
+    let mut p = Position::from_fen(fen);
     for i in 1..=6 {
         let start = Instant::now();
-        print!(
-            "Pertf({i}) = {} in ",
-            perft::perft(i, &p)
-        );
+        print!("Pertf({i}) = {} in ", perft::perft(i, &mut p));
         let duration = start.elapsed();
         println!("{:?}s", duration);
     }
-    return;
-    zeno::uci::uci_loop()
+}
+
+// zeno epd <file> [depth <plies>] [movetime <ms>] [nodes <count>] [threads <count>] [hash <mb>]
+fn run_epd(args: &[String]) {
+    let Some(path) = args.first() else {
+        println!("Usage: zeno epd <file> [depth N] [movetime MS] [nodes N] [threads N] [hash MB]");
+        return;
+    };
+    let mut limits = SearchLimits::default();
+    let mut options = SearchOptions::default();
+    let mut hash_size = DEFAULT_HASH_SIZE_MB;
+    for pair in args[1..].chunks(2) {
+        let value = pair.get(1).and_then(|value| value.parse::<u64>().ok());
+        match (pair[0].as_str(), value) {
+            ("depth", Some(depth)) => limits.depth = Some(depth as usize),
+            ("movetime", Some(movetime)) => limits.movetime = Some(movetime),
+            ("nodes", Some(nodes)) => limits.nodes = Some(nodes),
+            ("threads", Some(threads)) if threads > 0 => options.threads = threads as usize,
+            ("hash", Some(size)) if size > 0 => hash_size = size as usize,
+            _ => {
+                println!("Invalid argument: {}", pair.join(" "));
+                return;
+            }
+        }
+    }
+    // A second per position when no limit is given
+    if limits.depth.is_none() && limits.movetime.is_none() && limits.nodes.is_none() {
+        limits.movetime = Some(1000);
+    }
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            println!("Cannot open {}: {}", path, error);
+            return;
+        }
+    };
+    // Same stack as the searches started from the UCI loop
+    let suite = thread::Builder::new()
+        .stack_size(SEARCH_THREAD_STACK_SIZE)
+        .spawn(move || {
+            let transposition_table = TranspositionTable::new(hash_size);
+            epd::run_suite(
+                BufReader::new(file),
+                &limits,
+                &options,
+                &transposition_table,
+            );
+        })
+        .unwrap();
+    suite.join().unwrap();
 }
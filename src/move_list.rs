@@ -0,0 +1,128 @@
+use crate::utils::{Move, MoveType};
+use std::cmp::Reverse;
+use std::ops::{Index, IndexMut};
+
+// No position has more than 218 legal moves
+pub const MAX_MOVES: usize = 256;
+
+const NO_MOVE: Move = Move {
+    source: 0,
+    destination: 0,
+    move_type: MoveType::Normal,
+    move_score: 0,
+};
+
+// The moves generated for a position, stored on the stack
+#[derive(Clone, Copy, Debug)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    #[inline(always)]
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [NO_MOVE; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, mov: Move) {
+        self.moves[self.len] = mov;
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    #[inline(always)]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.as_mut_slice().swap(a, b);
+    }
+
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+
+    #[inline(always)]
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+
+    #[inline(always)]
+    pub fn iter(&self) -> std::slice::Iter<'_, Move> {
+        self.as_slice().iter()
+    }
+
+    #[inline(always)]
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Move> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    // The scores are not part of the move, only the squares and the move type are compared
+    #[inline(always)]
+    pub fn contains(&self, mov: &Move) -> bool {
+        self.iter().any(|m| m.to_u16() == mov.to_u16())
+    }
+
+    // Best scored moves first, moves of the same score keep their order
+    pub fn sort_by_score(&mut self) {
+        self.as_mut_slice()
+            .sort_by_key(|mov| Reverse(mov.move_score));
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+
+impl Index<usize> for MoveList {
+    type Output = Move;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Move {
+        &self.as_slice()[index]
+    }
+}
+
+impl IndexMut<usize> for MoveList {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Move {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Move, MAX_MOVES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter().take(self.len)
+    }
+}
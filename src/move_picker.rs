@@ -0,0 +1,292 @@
+use crate::evaluation;
+use crate::move_list::MoveList;
+use crate::moves_generator::{GenerationMode, generate_moves, is_pseudo_legal};
+use crate::position::Position;
+use crate::search::MAX_PLY;
+use crate::utils::{Move, PieceColor, PieceType};
+
+// https://www.chessprogramming.org/Move_Ordering
+// https://www.chessprogramming.org/History_Heuristic
+
+const MAX_HISTORY: i32 = 16_384;
+
+// Killer moves, butterfly history and countermoves, learned while searching
+pub struct HistoryTables {
+    killers: [[Option<Move>; 2]; MAX_PLY + 1],
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    counter_moves: Box<[[Option<Move>; 64]; 64]>,
+}
+
+impl HistoryTables {
+    pub fn new() -> HistoryTables {
+        HistoryTables {
+            killers: [[None; 2]; MAX_PLY + 1],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            counter_moves: Box::new([[None; 64]; 64]),
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers[ply.min(MAX_PLY)]
+    }
+
+    #[inline(always)]
+    pub fn get_counter_move(&self, previous_move: &Option<Move>) -> Option<Move> {
+        previous_move.and_then(|m| self.counter_moves[m.source as usize][m.destination as usize])
+    }
+
+    #[inline(always)]
+    pub fn get_history(&self, color: &PieceColor, mov: &Move) -> i32 {
+        self.history[color_index(color)][mov.source as usize][mov.destination as usize]
+    }
+
+    // A quiet move caused a beta cutoff: it is rewarded, and the quiet moves tried before it are penalized
+    pub fn update_quiet_cutoff(
+        &mut self,
+        color: &PieceColor,
+        ply: usize,
+        depth: usize,
+        mov: &Move,
+        previous_move: &Option<Move>,
+        tried_quiets: &[Move],
+    ) {
+        let killers = &mut self.killers[ply.min(MAX_PLY)];
+        if !is_same_move(&killers[0], mov) {
+            killers[1] = killers[0];
+            killers[0] = Some(*mov);
+        }
+
+        if let Some(previous_move) = previous_move {
+            self.counter_moves[previous_move.source as usize][previous_move.destination as usize] =
+                Some(*mov);
+        }
+
+        let bonus = (depth * depth).min(400) as i32;
+        self.update_history(color, mov, bonus);
+        for tried in tried_quiets {
+            if tried.to_u16() != mov.to_u16() {
+                self.update_history(color, tried, -bonus);
+            }
+        }
+    }
+
+    // The history gravity keeps the values within [-MAX_HISTORY, MAX_HISTORY]
+    #[inline(always)]
+    fn update_history(&mut self, color: &PieceColor, mov: &Move, bonus: i32) {
+        let entry =
+            &mut self.history[color_index(color)][mov.source as usize][mov.destination as usize];
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+}
+
+impl Default for HistoryTables {
+    fn default() -> Self {
+        HistoryTables::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Killers,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/*
+    The captures and queen promotions are generated first, the quiet moves and underpromotions
+    only once the killers are tried, after them in the same list:
+    [ bad captures | captures | quiets ]
+    Bad captures are moved at the front of the list as the good captures are picked
+*/
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    killer_index: usize,
+    counter_move: Option<Move>,
+    moves: MoveList,
+    cursor: usize,
+    end_bad_captures: usize,
+    end_captures: usize,
+}
+
+impl MovePicker {
+    pub fn new(
+        hash_move: Option<Move>,
+        killers: [Option<Move>; 2],
+        counter_move: Option<Move>,
+    ) -> MovePicker {
+        MovePicker {
+            stage: Stage::HashMove,
+            hash_move,
+            killers,
+            killer_index: 0,
+            counter_move,
+            moves: MoveList::new(),
+            cursor: 0,
+            end_bad_captures: 0,
+            end_captures: 0,
+        }
+    }
+
+    pub fn next(&mut self, position: &Position, history_tables: &HistoryTables) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    // A hash move coming from a key collision is dropped
+                    self.hash_move = self
+                        .hash_move
+                        .filter(|hash_move| is_pseudo_legal(position, hash_move));
+                    if self.hash_move.is_some() {
+                        return self.hash_move;
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    generate_moves(
+                        position,
+                        &position.get_turn(),
+                        &GenerationMode::Captures,
+                        &mut self.moves,
+                    );
+                    for mov in self.moves.iter_mut() {
+                        mov.move_score = evaluation::evaluate_move(position, mov);
+                    }
+                    self.end_captures = self.moves.len();
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    if self.cursor >= self.end_captures {
+                        self.stage = Stage::Killers;
+                        continue;
+                    }
+                    let mov = pick_best_move(
+                        &mut self.moves.as_mut_slice()[..self.end_captures],
+                        self.cursor,
+                    );
+                    if is_same_move(&self.hash_move, &mov) {
+                        self.cursor += 1;
+                        continue;
+                    }
+                    if !position.see_ge(&mov, 0) {
+                        self.moves.swap(self.cursor, self.end_bad_captures);
+                        self.end_bad_captures += 1;
+                        self.cursor += 1;
+                        continue;
+                    }
+                    self.cursor += 1;
+                    return Some(mov);
+                }
+                Stage::Killers => {
+                    if self.killer_index >= 2 {
+                        self.stage = Stage::CounterMove;
+                        continue;
+                    }
+                    let killer = self.killers[self.killer_index];
+                    self.killer_index += 1;
+                    if let Some(killer) = killer
+                        && self.is_special_quiet(position, &killer)
+                    {
+                        return Some(killer);
+                    }
+                    // Not played, so not to be skipped among the quiet moves
+                    self.killers[self.killer_index - 1] = None;
+                }
+                Stage::CounterMove => {
+                    self.stage = Stage::GenerateQuiets;
+                    if let Some(counter_move) = self.counter_move
+                        && !is_same_move(&self.killers[0], &counter_move)
+                        && !is_same_move(&self.killers[1], &counter_move)
+                        && self.is_special_quiet(position, &counter_move)
+                    {
+                        return Some(counter_move);
+                    }
+                    self.counter_move = None;
+                }
+                Stage::GenerateQuiets => {
+                    let color = position.get_turn();
+                    generate_moves(position, &color, &GenerationMode::Quiets, &mut self.moves);
+                    for mov in &mut self.moves.as_mut_slice()[self.end_captures..] {
+                        mov.move_score = history_tables.get_history(&color, mov);
+                    }
+                    self.cursor = self.end_captures;
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    if self.cursor >= self.moves.len() {
+                        self.cursor = 0;
+                        self.stage = Stage::BadCaptures;
+                        continue;
+                    }
+                    let mov = pick_best_move(self.moves.as_mut_slice(), self.cursor);
+                    self.cursor += 1;
+                    if !is_same_move(&self.hash_move, &mov)
+                        && !is_same_move(&self.killers[0], &mov)
+                        && !is_same_move(&self.killers[1], &mov)
+                        && !is_same_move(&self.counter_move, &mov)
+                    {
+                        return Some(mov);
+                    }
+                }
+                Stage::BadCaptures => {
+                    if self.cursor >= self.end_bad_captures {
+                        self.stage = Stage::Done;
+                        continue;
+                    }
+                    let mov = self.moves[self.cursor];
+                    self.cursor += 1;
+                    return Some(mov);
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    // Killers and countermoves come from other positions, they must be quiet moves of this one
+    #[inline(always)]
+    fn is_special_quiet(&self, position: &Position, mov: &Move) -> bool {
+        !is_same_move(&self.hash_move, mov)
+            && is_quiet(position, mov)
+            && is_pseudo_legal(position, mov)
+    }
+}
+
+#[inline(always)]
+pub fn is_quiet(position: &Position, mov: &Move) -> bool {
+    mov.move_type.promotion_piece().is_none() && position.get_captured_piece(mov) == PieceType::None
+}
+
+#[inline(always)]
+fn is_same_move(a: &Option<Move>, b: &Move) -> bool {
+    a.is_some_and(|a| a.to_u16() == b.to_u16())
+}
+
+#[inline(always)]
+fn color_index(color: &PieceColor) -> usize {
+    match color {
+        PieceColor::Black => 1,
+        _ => 0,
+    }
+}
+
+// Selection sort step: brings the best scored of the remaining moves at the given index
+#[inline(always)]
+pub fn pick_best_move(moves: &mut [Move], index: usize) -> Move {
+    let mut best_index = index;
+    let mut best_score = i32::MIN;
+    for (i, mov) in moves.iter().enumerate().skip(index) {
+        if mov.move_score > best_score {
+            best_score = mov.move_score;
+            best_index = i;
+        }
+    }
+    moves.swap(index, best_index);
+    moves[index]
+}
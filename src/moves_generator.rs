@@ -1,103 +1,349 @@
 use crate::lookup_tables;
-use crate::position::Position;
+use crate::move_list::MoveList;
+use crate::position::{Position, castling_targets};
 use crate::utils::{Move, MoveType, Piece, PieceColor, PieceType};
 
+// https://www.chessprogramming.org/Move_Generation#Staged_Move_Generation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GenerationMode {
+    All,
+    // Captures, including en passant, and promotions to a queen
+    Captures,
+    // Moves to empty squares, castling and underpromotions
+    Quiets,
+    // Only meant to be used in check: moves of the king, and captures or blocks of a single checker
+    Evasions,
+    // Moves to empty squares giving a direct or a discovered check, castling and promotions aside
+    QuietChecks,
+}
+
+const ALL_PROMOTIONS: [MoveType; 4] = [
+    MoveType::PawnToKnight,
+    MoveType::PawnToBishop,
+    MoveType::PawnToRook,
+    MoveType::PawnToQueen,
+];
+const UNDERPROMOTIONS: [MoveType; 3] = [
+    MoveType::PawnToKnight,
+    MoveType::PawnToBishop,
+    MoveType::PawnToRook,
+];
+const QUEEN_PROMOTION: [MoveType; 1] = [MoveType::PawnToQueen];
+pub const PROMOTION_RANKS: u64 = 0xFF00_0000_0000_00FF;
+
+// The moves are appended to the list
 #[inline(always)]
-pub fn generate_pseudo_legal_moves(position: &Position, color: &PieceColor) -> [Option<Move>; 256] {
-    let mut moves = [None; 256];
-    let mut cursor = 0;
-    let coords = position.get_available_piece_coords(color);
-    let en_passant = position.get_en_passant().unwrap_or(-1);
+pub fn generate_pseudo_legal_moves(position: &Position, color: &PieceColor, moves: &mut MoveList) {
+    generate_moves(position, color, &GenerationMode::All, moves);
+}
 
-    let mut no_short_castle_manage = position.can_short_castle(color);
-    let mut no_long_castle_manage = position.can_long_castle(color);
+// The pseudo-legal moves of the mode, appended to the list
+pub fn generate_moves(
+    position: &Position,
+    color: &PieceColor,
+    mode: &GenerationMode,
+    moves: &mut MoveList,
+) {
+    let table = &lookup_tables::LOOK_UP_TABLE;
+    let board = position.get_board();
+    let (own_board, opponent_board) = match color {
+        PieceColor::None => return,
+        PieceColor::White => (position.get_white_board(), position.get_black_board()),
+        PieceColor::Black => (position.get_black_board(), position.get_white_board()),
+    };
+    let en_passant = position.get_en_passant().unwrap_or(-1);
+    let en_passant_mask = if en_passant >= 0 {
+        1u64 << en_passant
+    } else {
+        0
+    };
 
-    for source in coords {
-        let source = match source {
-            None => break,
-            Some(s) => s,
+    // Out of check, the checker has to be captured or the check blocked, in double check
+    // only the king can move
+    let mut evasion_mask = !0;
+    if *mode == GenerationMode::Evasions {
+        let king = position.get_king_coord(color);
+        let checkers = checkers_of(position, color, &king);
+        evasion_mask = match checkers.count_ones() {
+            0 => !0,
+            1 => table.between[king as usize][checkers.trailing_zeros() as usize] | checkers,
+            _ => 0,
         };
+        // The pawn giving check may be taken en passant
+        let captured = match color {
+            PieceColor::White => en_passant - 8,
+            _ => en_passant + 8,
+        };
+        if en_passant >= 0 && checkers & (1u64 << captured) != 0 {
+            evasion_mask |= en_passant_mask;
+        }
+    }
+
+    // The squares from which each piece type attacks the opponent king
+    let opponent_king = position.get_king_coord(&color.opposite());
+    let mut check_masks = [0u64; 6];
+    let mut discovered_checks = 0;
+    if *mode == GenerationMode::QuietChecks {
+        let index = opponent_king as usize;
+        let bishop_checks = generate_move_mask_for_bishop(&board, &opponent_king);
+        let rook_checks = generate_move_mask_for_rook(&board, &opponent_king);
+        check_masks = [
+            match color {
+                PieceColor::White => table.black_pawn_attacks[index],
+                _ => table.white_pawn_attacks[index],
+            },
+            table.knight_attacks[index],
+            bishop_checks,
+            rook_checks,
+            bishop_checks | rook_checks,
+            0,
+        ];
+        discovered_checks = position.blockers_for_king(&color.opposite()) & own_board;
+    }
+
+    let mut pieces = own_board;
+    while pieces != 0 {
+        let source = pieces.trailing_zeros() as i8;
+        pieces &= pieces - 1;
 
         let piece = position.get_piece_on_square(&source);
-        let mut mask = generate_mask_moves(&position, &source, &piece);
-        while let Some(destination) = (mask != 0).then(|| mask.trailing_zeros() as i8) {
-            let destination_rank = 1 + (destination / 8);
-            match piece.piece_type {
-                PieceType::Pawn => {
-                    if destination == en_passant {
-                        moves[cursor] = Some(Move {
-                            source,
-                            destination,
-                            move_type: MoveType::EnPassant,
-                            move_score: 0,
-                        });
-                        mask &= mask - 1;
-                        cursor += 1;
-                        continue;
-                    } else {
-                        if destination_rank == 1 || destination_rank == 8 {
-                            for promo in [
-                                MoveType::PawnToKnight,
-                                MoveType::PawnToBishop,
-                                MoveType::PawnToRook,
-                                MoveType::PawnToQueen,
-                            ] {
-                                moves[cursor] = Some(Move {
-                                    source,
-                                    destination,
-                                    move_type: promo,
-                                    move_score: 0,
-                                });
-                                cursor += 1;
-                            }
-                            mask &= mask - 1;
-                            continue;
-                        }
-                    }
+        let mask = generate_mask_moves(position, &source, &piece);
+        let mut check_mask = check_masks[piece_index(&piece.piece_type)];
+        // Leaving the line between a slider and the opponent king uncovers a check
+        if discovered_checks & (1u64 << source) != 0 {
+            check_mask |= !table.line[opponent_king as usize][source as usize];
+        }
+
+        if piece.piece_type == PieceType::Pawn {
+            let pushes = mask & !opponent_board & !en_passant_mask;
+            let promotions = mask & PROMOTION_RANKS;
+            let (mask, promotion_types): (u64, &[MoveType]) = match mode {
+                GenerationMode::All => (mask, &ALL_PROMOTIONS),
+                GenerationMode::Captures => (
+                    (mask & !pushes & !PROMOTION_RANKS) | promotions,
+                    &QUEEN_PROMOTION,
+                ),
+                GenerationMode::Quiets => {
+                    ((pushes & !PROMOTION_RANKS) | promotions, &UNDERPROMOTIONS)
                 }
-                PieceType::King => {
-                    if no_short_castle_manage {
-                        moves[cursor] = Some(Move {
-                            source,
-                            destination: source + 2,
-                            move_type: MoveType::ShortCastle,
-                            move_score: 0,
-                        });
-                        cursor += 1;
-                        no_short_castle_manage = false;
-                    }
-                    if no_long_castle_manage {
-                        moves[cursor] = Some(Move {
-                            source,
-                            destination: source - 2,
-                            move_type: MoveType::LongCastle,
-                            move_score: 0,
-                        });
-                        cursor += 1;
-                        no_long_castle_manage = false;
-                    }
+                GenerationMode::Evasions => (mask & evasion_mask, &ALL_PROMOTIONS),
+                GenerationMode::QuietChecks => (pushes & !PROMOTION_RANKS & check_mask, &[]),
+            };
+            add_moves(moves, source, mask, en_passant, promotion_types);
+            continue;
+        }
+
+        let mask = match mode {
+            GenerationMode::All => mask,
+            GenerationMode::Captures => mask & opponent_board,
+            GenerationMode::Quiets => mask & !board,
+            GenerationMode::Evasions if piece.piece_type == PieceType::King => mask,
+            GenerationMode::Evasions => mask & evasion_mask,
+            GenerationMode::QuietChecks => mask & !board & check_mask,
+        };
+        add_moves(moves, source, mask, -1, &[]);
+    }
+
+    if matches!(mode, GenerationMode::All | GenerationMode::Quiets) {
+        add_castling_moves(position, color, moves);
+    }
+}
+
+// Whether a move coming from the transposition table or from another position is one of the
+// pseudo-legal moves of the side to move
+pub fn is_pseudo_legal(position: &Position, mov: &Move) -> bool {
+    let turn = position.get_turn();
+    let piece = position.get_piece_on_square(&mov.source);
+    if piece.color != turn {
+        return false;
+    }
+
+    let destination_mask = 1u64 << mov.destination;
+    match mov.move_type {
+        MoveType::ShortCastle | MoveType::LongCastle => {
+            piece.piece_type == PieceType::King
+                && mov.destination == castling_targets(&turn, &mov.move_type).0
+                && position.can_castle(&turn, &mov.move_type)
+        }
+        _ if generate_mask_moves(position, &mov.source, &piece) & destination_mask == 0 => false,
+        // The move type has to be the one the generator gives to the move
+        _ if piece.piece_type == PieceType::Pawn => {
+            if position.get_en_passant() == Some(mov.destination) {
+                mov.move_type == MoveType::EnPassant
+            } else if destination_mask & PROMOTION_RANKS != 0 {
+                mov.move_type.promotion_piece().is_some()
+            } else {
+                mov.move_type == MoveType::Normal
+            }
+        }
+        _ => mov.move_type == MoveType::Normal,
+    }
+}
+
+// Only the legal moves: the checkers and the pinned pieces are found first, so that no move
+// has to be played to know whether it leaves the king in check
+pub fn generate_legal_moves(position: &Position, color: &PieceColor, moves: &mut MoveList) {
+    let board = position.get_board();
+    let (own_board, opponent_board) = match color {
+        PieceColor::None => return,
+        PieceColor::White => (position.get_white_board(), position.get_black_board()),
+        PieceColor::Black => (position.get_black_board(), position.get_white_board()),
+    };
+    let king = position.get_king_coord(color);
+    let checkers = checkers_of(position, color, &king);
+    let pinned = position.pinned(color);
+
+    // The king must not stay on the line of a slider by stepping back along it
+    let board_without_king = board ^ (1u64 << king);
+    let mut mask = lookup_tables::LOOK_UP_TABLE.king_attacks[king as usize] & !own_board;
+    let mut king_mask = 0;
+    while mask != 0 {
+        let destination = mask.trailing_zeros() as i8;
+        if position.attackers_to(&destination, &board_without_king) & opponent_board == 0 {
+            king_mask |= 1u64 << destination;
+        }
+        mask &= mask - 1;
+    }
+    add_moves(moves, king, king_mask, -1, &[]);
+
+    // Only the king can get out of a double check
+    if checkers.count_ones() > 1 {
+        return;
+    }
+
+    // Out of a single check, the checker has to be captured or the check blocked
+    let target_mask = match checkers {
+        0 => !0,
+        _ => {
+            lookup_tables::LOOK_UP_TABLE.between[king as usize][checkers.trailing_zeros() as usize]
+                | checkers
+        }
+    };
+
+    let en_passant = position.get_en_passant().unwrap_or(-1);
+    let mut pieces = own_board & !position.get_kings_board();
+    while pieces != 0 {
+        let source = pieces.trailing_zeros() as i8;
+        pieces &= pieces - 1;
+
+        let piece = position.get_piece_on_square(&source);
+        let moves_mask = generate_mask_moves(position, &source, &piece);
+        let mut mask = moves_mask & target_mask;
+        // A pinned piece can only move along the line between its king and the pinning piece
+        if pinned & (1u64 << source) != 0 {
+            mask &= lookup_tables::LOOK_UP_TABLE.line[king as usize][source as usize];
+        }
+
+        let is_pawn = piece.piece_type == PieceType::Pawn;
+        if is_pawn && en_passant >= 0 {
+            mask &= !(1u64 << en_passant);
+            // Both pawns leave the rank of the king at once, the capture is tried on the board
+            if moves_mask & (1u64 << en_passant) != 0 {
+                let captured = match color {
+                    PieceColor::White => en_passant - 8,
+                    _ => en_passant + 8,
+                };
+                let board_after =
+                    board ^ (1u64 << source) ^ (1u64 << en_passant) ^ (1u64 << captured);
+                let attackers = position.attackers_to(&king, &board_after)
+                    & opponent_board
+                    & !(1u64 << captured);
+                if attackers == 0 {
+                    mask |= 1u64 << en_passant;
                 }
-                _ => {}
             }
+        }
+        if is_pawn {
+            add_moves(moves, source, mask, en_passant, &ALL_PROMOTIONS);
+        } else {
+            add_moves(moves, source, mask, -1, &[]);
+        }
+    }
+
+    if checkers == 0 {
+        add_castling_moves(position, color, moves);
+    }
+}
+
+// Pawns moving to the last rank promote to each of the given piece types, the promotions
+// are left out for the other pieces
+#[inline(always)]
+fn add_moves(
+    moves: &mut MoveList,
+    source: i8,
+    mut mask: u64,
+    en_passant: i8,
+    promotion_types: &[MoveType],
+) {
+    while mask != 0 {
+        let destination = mask.trailing_zeros() as i8;
+        mask &= mask - 1;
+        let move_type = if destination == en_passant {
+            MoveType::EnPassant
+        } else if (1u64 << destination) & PROMOTION_RANKS != 0 && !promotion_types.is_empty() {
+            for promotion_type in promotion_types {
+                moves.push(Move {
+                    source,
+                    destination,
+                    move_type: *promotion_type,
+                    move_score: 0,
+                });
+            }
+            continue;
+        } else {
+            MoveType::Normal
+        };
+
+        moves.push(Move {
+            source,
+            destination,
+            move_type,
+            move_score: 0,
+        });
+    }
+}
 
-            moves[cursor] = Some(Move {
-                source,
-                destination,
-                move_type: MoveType::Normal,
+// The king goes to the g or c file, which may be where it already stands in Chess960
+#[inline(always)]
+fn add_castling_moves(position: &Position, color: &PieceColor, moves: &mut MoveList) {
+    for castling_type in [MoveType::ShortCastle, MoveType::LongCastle] {
+        if position.can_castle(color, &castling_type) {
+            moves.push(Move {
+                source: position.get_king_coord(color),
+                destination: castling_targets(color, &castling_type).0,
+                move_type: castling_type,
                 move_score: 0,
             });
-            cursor += 1;
-            mask &= mask - 1;
         }
     }
-    moves
+}
+
+// The checkers of the side to move are known by the position
+#[inline(always)]
+fn checkers_of(position: &Position, color: &PieceColor, king: &i8) -> u64 {
+    if *color == position.get_turn() {
+        return position.checkers();
+    }
+    position.attackers_to(king, &position.get_board()) & position.get_color_board(&color.opposite())
+}
+
+#[inline(always)]
+fn piece_index(piece_type: &PieceType) -> usize {
+    match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        _ => 5,
+    }
 }
 
 #[inline(always)]
 pub fn generate_mask_moves(position: &Position, source: &i8, piece: &Piece) -> u64 {
     let mut attacks_squares: u64 = match piece.piece_type {
         PieceType::None => 0,
-        PieceType::Pawn => generate_move_mask_for_pawn(&position, source, &piece.color),
+        PieceType::Pawn => generate_move_mask_for_pawn(position, source, &piece.color),
         PieceType::Knight => lookup_tables::LOOK_UP_TABLE.knight_attacks[*source as usize],
         PieceType::Bishop => generate_move_mask_for_bishop(&position.get_board(), source),
         PieceType::Rook => generate_move_mask_for_rook(&position.get_board(), source),
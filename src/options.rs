@@ -0,0 +1,195 @@
+use std::fmt;
+
+// https://backscattering.de/chess/uci/#engine-option
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionType {
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Check {
+        default: bool,
+    },
+    Combo {
+        default: &'static str,
+        vars: &'static [&'static str],
+    },
+    Button,
+    String {
+        default: &'static str,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValue {
+    Spin(i64),
+    Check(bool),
+    Combo(String),
+    Button,
+    String(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionError {
+    UnknownOption(String),
+    InvalidValue { name: String, value: String },
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::UnknownOption(name) => write!(f, "Unknown option: {}", name),
+            OptionError::InvalidValue { name, value } => {
+                write!(f, "Invalid value for option {}: {}", name, value)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UciOption {
+    name: &'static str,
+    option_type: OptionType,
+    value: OptionValue,
+}
+
+impl UciOption {
+    pub fn new(name: &'static str, option_type: OptionType) -> UciOption {
+        let value = match &option_type {
+            OptionType::Spin { default, .. } => OptionValue::Spin(*default),
+            OptionType::Check { default } => OptionValue::Check(*default),
+            OptionType::Combo { default, .. } => OptionValue::Combo(default.to_string()),
+            OptionType::Button => OptionValue::Button,
+            OptionType::String { default } => OptionValue::String(default.to_string()),
+        };
+        UciOption {
+            name,
+            option_type,
+            value,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_value(&self) -> &OptionValue {
+        &self.value
+    }
+
+    // Checks the value against the type of the option, spin values are clamped to their bounds
+    fn parse_value(&self, value: &str) -> Option<OptionValue> {
+        match &self.option_type {
+            OptionType::Spin { min, max, .. } => value
+                .parse::<i64>()
+                .ok()
+                .map(|v| OptionValue::Spin(v.clamp(*min, *max))),
+            OptionType::Check { .. } => match value {
+                "true" => Some(OptionValue::Check(true)),
+                "false" => Some(OptionValue::Check(false)),
+                _ => None,
+            },
+            OptionType::Combo { vars, .. } => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .map(|var| OptionValue::Combo(var.to_string())),
+            OptionType::Button => Some(OptionValue::Button),
+            OptionType::String { .. } => Some(OptionValue::String(value.to_string())),
+        }
+    }
+}
+
+// The line sent to the GUI in answer to the uci command
+impl fmt::Display for UciOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match &self.option_type {
+            OptionType::Spin { default, min, max } => {
+                write!(f, "spin default {} min {} max {}", default, min, max)
+            }
+            OptionType::Check { default } => write!(f, "check default {}", default),
+            OptionType::Combo { default, vars } => {
+                write!(f, "combo default {}", default)?;
+                for var in vars.iter() {
+                    write!(f, " var {}", var)?;
+                }
+                Ok(())
+            }
+            OptionType::Button => write!(f, "button"),
+            OptionType::String { default } => {
+                // An empty string has to be sent as <empty>
+                let default = if default.is_empty() {
+                    "<empty>"
+                } else {
+                    default
+                };
+                write!(f, "string default {}", default)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UciOptions {
+    options: Vec<UciOption>,
+}
+
+impl UciOptions {
+    pub fn new() -> UciOptions {
+        UciOptions::default()
+    }
+
+    pub fn add(&mut self, name: &'static str, option_type: OptionType) {
+        self.options.push(UciOption::new(name, option_type));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &UciOption> {
+        self.options.iter()
+    }
+
+    // Option names are case insensitive, the name as registered is returned
+    pub fn set(&mut self, name: &str, value: &str) -> Result<&'static str, OptionError> {
+        let option = self
+            .options
+            .iter_mut()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| OptionError::UnknownOption(name.to_string()))?;
+        option.value = option
+            .parse_value(value)
+            .ok_or_else(|| OptionError::InvalidValue {
+                name: option.name.to_string(),
+                value: value.to_string(),
+            })?;
+        Ok(option.name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        self.options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .map(|option| &option.value)
+    }
+
+    pub fn get_spin(&self, name: &str) -> i64 {
+        match self.get(name) {
+            Some(OptionValue::Spin(value)) => *value,
+            _ => panic!("No spin option named {}", name),
+        }
+    }
+
+    pub fn get_check(&self, name: &str) -> bool {
+        match self.get(name) {
+            Some(OptionValue::Check(value)) => *value,
+            _ => panic!("No check option named {}", name),
+        }
+    }
+
+    pub fn get_string(&self, name: &str) -> &str {
+        match self.get(name) {
+            Some(OptionValue::Combo(value)) | Some(OptionValue::String(value)) => value,
+            _ => panic!("No combo or string option named {}", name),
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use crate::position::{FenError, Position};
+use crate::san::SanError;
+use crate::search::{MATE, MATE_BOUND};
+use crate::uci::STARTPOS;
+use crate::utils::{Move, PieceColor, UndoMove};
+use std::fmt;
+use std::io::BufRead;
+
+// https://www.saremba.de/chessgml/standards/pgn/pgn-complete.htm
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const MAX_LINE_LENGTH: usize = 79;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PgnErrorKind {
+    InvalidTag(String),
+    InvalidFen(FenError),
+    IllegalMove(SanError),
+    UnterminatedComment,
+    UnbalancedVariation,
+    VariationWithoutMove,
+    Io(String),
+}
+
+// The line of the error counts from 1 from the start of the stream
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnError {
+    pub line: usize,
+    pub kind: PgnErrorKind,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
+        match &self.kind {
+            PgnErrorKind::InvalidTag(tag) => write!(f, "Invalid tag pair: {}", tag),
+            PgnErrorKind::InvalidFen(error) => write!(f, "Invalid FEN tag: {}", error),
+            PgnErrorKind::IllegalMove(error) => write!(f, "{}", error),
+            PgnErrorKind::UnterminatedComment => write!(f, "Unterminated comment"),
+            PgnErrorKind::UnbalancedVariation => write!(f, "Unbalanced variation"),
+            PgnErrorKind::VariationWithoutMove => write!(f, "Variation without a move to replace"),
+            PgnErrorKind::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub mov: Move,
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    pub variations: Vec<PgnVariation>,
+}
+
+// A variation replaces the move it is attached to, its moves are played from the position
+// before that move
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnVariation {
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    start_position: Position,
+    position: Position,
+    moves: Vec<PgnMove>,
+    comment: Option<String>,
+}
+
+impl PgnGame {
+    // The FEN and SetUp tags are added when the game does not start from the initial position
+    pub fn new(start_position: Position) -> PgnGame {
+        let mut game = PgnGame {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            start_position: start_position.clone(),
+            position: start_position.clone(),
+            moves: Vec::new(),
+            comment: None,
+        };
+        if start_position.to_fen() != STARTPOS {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &start_position.to_fen());
+        }
+        game
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn get_tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    // The moves of the main line, each one with its variations
+    pub fn get_moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    pub fn get_start_position(&self) -> &Position {
+        &self.start_position
+    }
+
+    // The position at the end of the main line
+    pub fn get_position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn get_result(&self) -> &str {
+        self.get_tag("Result").unwrap_or("*")
+    }
+
+    pub fn set_result(&mut self, result: &str) {
+        self.set_tag("Result", result);
+    }
+
+    // The move has to be legal in the current position
+    pub fn push_move(&mut self, mov: &Move, comment: Option<String>) {
+        let san = self.position.move_to_san(mov);
+        self.position.make_move(mov, true);
+        self.moves.push(PgnMove {
+            mov: *mov,
+            san,
+            nags: Vec::new(),
+            comment,
+            variations: Vec::new(),
+        });
+    }
+
+    fn set_main_line(&mut self, main_line: OpenLine) {
+        self.moves = main_line.variation.moves;
+        self.comment = main_line.variation.comment;
+        self.position = main_line.position;
+    }
+}
+
+// A line of moves being read, the main line or a variation, with the position after its last move
+struct OpenLine {
+    variation: PgnVariation,
+    position: Position,
+    undo_move: Option<UndoMove>,
+}
+
+impl OpenLine {
+    fn new(position: Position) -> OpenLine {
+        OpenLine {
+            variation: PgnVariation {
+                comment: None,
+                moves: Vec::new(),
+            },
+            position,
+            undo_move: None,
+        }
+    }
+
+    fn push_san(&mut self, san: &str) -> Result<(), SanError> {
+        let mov = self.position.parse_san(san)?;
+        let san = self.position.move_to_san(&mov);
+        self.undo_move = Some(self.position.make_move(&mov, true));
+        self.variation.moves.push(PgnMove {
+            mov,
+            san,
+            nags: Vec::new(),
+            comment: None,
+            variations: Vec::new(),
+        });
+        Ok(())
+    }
+
+    // A comment before the first move belongs to the line itself
+    fn add_comment(&mut self, comment: &str) {
+        let target = match self.variation.moves.last_mut() {
+            Some(pgn_move) => &mut pgn_move.comment,
+            None => &mut self.variation.comment,
+        };
+        match target {
+            Some(text) => {
+                text.push(' ');
+                text.push_str(comment);
+            }
+            None => *target = Some(comment.to_string()),
+        }
+    }
+
+    // The position before the last move, from which its variations are played
+    fn variation_start(&self) -> Option<Position> {
+        let mut position = self.position.clone();
+        position.unmake_move(self.undo_move.as_ref()?);
+        Some(position)
+    }
+}
+
+// The writer follows the export format: Seven Tag Roster first, lines of at most 79 characters
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, _) in SEVEN_TAG_ROSTER.iter() {
+            writeln!(
+                f,
+                "[{} \"{}\"]",
+                name,
+                escape(self.get_tag(name).unwrap_or("?"))
+            )?;
+        }
+        for (name, value) in self.tags.iter() {
+            if !SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name) {
+                writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+            }
+        }
+        writeln!(f)?;
+
+        let mut tokens = vec![];
+        if let Some(comment) = &self.comment {
+            tokens.push(format!("{{ {} }}", comment));
+        }
+        push_move_tokens(
+            &mut tokens,
+            &self.moves,
+            self.start_position.get_full_move_number(),
+            self.start_position.get_turn(),
+        );
+        tokens.push(self.get_result().to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+// The variations of a move follow it, their first move is numbered as the move they replace
+fn push_move_tokens(
+    tokens: &mut Vec<String>,
+    moves: &[PgnMove],
+    mut number_of_move: u16,
+    mut turn: PieceColor,
+) {
+    // The move number of a black move is repeated after a comment or a variation
+    let mut show_number = true;
+    for pgn_move in moves.iter() {
+        match turn {
+            PieceColor::White => tokens.push(format!("{}.", number_of_move)),
+            _ if show_number => tokens.push(format!("{}...", number_of_move)),
+            _ => {}
+        }
+        tokens.push(pgn_move.san.clone());
+        for nag in pgn_move.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+        show_number = pgn_move.comment.is_some() || !pgn_move.variations.is_empty();
+        if let Some(comment) = &pgn_move.comment {
+            tokens.push(format!("{{ {} }}", comment));
+        }
+        for variation in pgn_move.variations.iter() {
+            tokens.push("(".to_string());
+            if let Some(comment) = &variation.comment {
+                tokens.push(format!("{{ {} }}", comment));
+            }
+            push_move_tokens(tokens, &variation.moves, number_of_move, turn);
+            tokens.push(")".to_string());
+        }
+        if turn == PieceColor::Black {
+            number_of_move += 1;
+        }
+        turn = turn.opposite();
+    }
+}
+
+// Search score of the side to move written from the point of view of White, in pawns or as a mate
+pub fn eval_comment(score: i32, turn: &PieceColor) -> String {
+    let score = match turn {
+        PieceColor::Black => -score,
+        _ => score,
+    };
+    let eval = if score >= MATE_BOUND {
+        format!("#{}", (MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        format!("#-{}", (MATE + score + 1) / 2)
+    } else {
+        format!("{:.2}", score as f64 / 100.0)
+    };
+    format!("[%eval {}]", eval)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Reads the games one after the other from any buffered input, such as a file or the standard input
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line_number: usize,
+    // A tag line already read, which starts the next game
+    pending_line: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            reader,
+            line_number: 0,
+            pending_line: None,
+        }
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>, PgnError> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line));
+        }
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.line_number += 1;
+                Ok(Some(line.trim_end().to_string()))
+            }
+            Err(error) => Err(self.error(PgnErrorKind::Io(error.to_string()))),
+        }
+    }
+
+    fn error(&self, kind: PgnErrorKind) -> PgnError {
+        PgnError {
+            line: self.line_number,
+            kind,
+        }
+    }
+
+    // After an error, the rest of the game is skipped up to its result, or up to tags following
+    // an empty line, as the tags of the broken game itself may come next
+    fn skip_game(&mut self) {
+        let mut after_empty_line = false;
+        while let Ok(Some(line)) = self.next_line() {
+            let line = line.trim();
+            if line.is_empty() {
+                after_empty_line = true;
+                continue;
+            }
+            if after_empty_line && line.starts_with('[') {
+                self.pending_line = Some(line.to_string());
+                return;
+            }
+            if !line.starts_with('[')
+                && line
+                    .split_whitespace()
+                    .last()
+                    .is_some_and(|token| RESULTS.contains(&token))
+            {
+                return;
+            }
+            after_empty_line = false;
+        }
+    }
+
+    fn read_game(&mut self) -> Result<Option<PgnGame>, PgnError> {
+        let mut tags: Vec<(String, String)> = vec![];
+        let mut line =
+            loop {
+                match self.next_line()? {
+                    None => return Ok(None),
+                    Some(line) => {
+                        let line = line.trim().to_string();
+                        // Lines starting with % are escaped from the PGN data
+                        if line.is_empty() || line.starts_with('%') {
+                            continue;
+                        }
+                        if !line.starts_with('[') {
+                            break line;
+                        }
+                        tags.push(parse_tag(&line).ok_or_else(|| {
+                            self.error(PgnErrorKind::InvalidTag(line.to_string()))
+                        })?);
+                    }
+                }
+            };
+
+        let start_position = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Position::try_from_fen(fen)
+                .map_err(|error| self.error(PgnErrorKind::InvalidFen(error)))?,
+            None => Position::from_fen(STARTPOS),
+        };
+        let mut game = PgnGame::new(start_position.clone());
+        for (name, value) in tags.iter() {
+            game.set_tag(name, value);
+        }
+
+        let mut comment: Option<String> = None;
+        // The main line first, then the variations being read, the innermost one last
+        let mut open_lines = vec![OpenLine::new(start_position)];
+        loop {
+            let mut chars = line.char_indices().peekable();
+            while let Some((index, ch)) = chars.next() {
+                if let Some(text) = comment.as_mut() {
+                    match ch {
+                        '}' => {
+                            open_lines.last_mut().unwrap().add_comment(text.trim());
+                            comment = None;
+                        }
+                        _ => text.push(ch),
+                    }
+                    continue;
+                }
+                match ch {
+                    '{' => comment = Some(String::new()),
+                    // Comment up to the end of the line
+                    ';' => {
+                        open_lines
+                            .last_mut()
+                            .unwrap()
+                            .add_comment(line[index + 1..].trim());
+                        break;
+                    }
+                    '(' => {
+                        let position = open_lines
+                            .last()
+                            .unwrap()
+                            .variation_start()
+                            .ok_or_else(|| self.error(PgnErrorKind::VariationWithoutMove))?;
+                        open_lines.push(OpenLine::new(position));
+                    }
+                    ')' => {
+                        if open_lines.len() == 1 {
+                            return Err(self.error(PgnErrorKind::UnbalancedVariation));
+                        }
+                        let variation = open_lines.pop().unwrap().variation;
+                        let parent = open_lines.last_mut().unwrap();
+                        let pgn_move = parent.variation.moves.last_mut().unwrap();
+                        pgn_move.variations.push(variation);
+                    }
+                    _ if ch.is_whitespace() => {}
+                    _ => {
+                        let mut end = index + ch.len_utf8();
+                        while let Some((next_index, next)) = chars.peek() {
+                            if next.is_whitespace() || "{}();".contains(*next) {
+                                break;
+                            }
+                            end = next_index + next.len_utf8();
+                            chars.next();
+                        }
+                        let token = &line[index..end];
+                        if RESULTS.contains(&token) {
+                            if open_lines.len() > 1 {
+                                return Err(self.error(PgnErrorKind::UnbalancedVariation));
+                            }
+                            game.set_main_line(open_lines.pop().unwrap());
+                            game.set_result(token);
+                            return Ok(Some(game));
+                        }
+                        let open_line = open_lines.last_mut().unwrap();
+                        if let Some(nag) = token.strip_prefix('$') {
+                            if let (Ok(nag), Some(pgn_move)) =
+                                (nag.parse::<u8>(), open_line.variation.moves.last_mut())
+                            {
+                                pgn_move.nags.push(nag);
+                            }
+                            continue;
+                        }
+                        // Move numbers may be attached to the move: 12.e4 or 12...e5
+                        let san = match token.rfind('.') {
+                            Some(dot) => &token[dot + 1..],
+                            None => token,
+                        };
+                        if san.is_empty() {
+                            continue;
+                        }
+                        open_line
+                            .push_san(san)
+                            .map_err(|error| self.error(PgnErrorKind::IllegalMove(error)))?;
+                    }
+                }
+            }
+            if let Some(text) = comment.as_mut() {
+                text.push(' ');
+            }
+
+            line = match self.next_line()? {
+                Some(line) => {
+                    // The movetext of a game without result ends at the tags of the next one
+                    if comment.is_none() && open_lines.len() == 1 && line.starts_with('[') {
+                        self.pending_line = Some(line);
+                        game.set_main_line(open_lines.pop().unwrap());
+                        return Ok(Some(game));
+                    }
+                    line
+                }
+                None if comment.is_some() => {
+                    return Err(self.error(PgnErrorKind::UnterminatedComment));
+                }
+                None if open_lines.len() > 1 => {
+                    return Err(self.error(PgnErrorKind::UnbalancedVariation));
+                }
+                None => {
+                    game.set_main_line(open_lines.pop().unwrap());
+                    return Ok(Some(game));
+                }
+            };
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_game() {
+            Ok(game) => game.map(Ok),
+            Err(error) => {
+                self.skip_game();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+// [Name "Value"], with \" and \\ escaped in the value
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => unescaped.push(chars.next()?),
+            _ => unescaped.push(ch),
+        }
+    }
+    Some((name.to_string(), unescaped))
+}
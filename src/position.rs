@@ -1,9 +1,13 @@
 use crate::lookup_tables;
+use crate::move_list::MoveList;
 use crate::moves_generator::{
-    generate_mask_moves, generate_move_mask_for_bishop,
+    PROMOTION_RANKS, generate_legal_moves, generate_mask_moves, generate_move_mask_for_bishop,
     generate_move_mask_for_rook,
 };
-use crate::utils::{Move, MoveType, Piece, PieceColor, PieceType};
+use crate::utils::{Move, MoveType, Piece, PieceColor, PieceType, UndoMove};
+use crate::zobrist_hash;
+use std::fmt;
+use std::str::FromStr;
 /*
     Directions and shifts
     +-----+-----+-----+
@@ -28,7 +32,52 @@ use crate::utils::{Move, MoveType, Piece, PieceColor, PieceType};
      File A
 */
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError {
+    MissingField(&'static str),
+    WrongNumberOfRanks(usize),
+    InvalidPiece(char),
+    WrongRankLength(u8),
+    MissingKing(PieceColor),
+    TooManyKings(PieceColor),
+    PawnOnBackRank(i8),
+    InvalidTurn(String),
+    InvalidCastling(String),
+    InconsistentCastlingRights(char),
+    ImpossibleEnPassant(String),
+    InvalidMoveCounter(String),
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "Missing {} field", field),
+            FenError::WrongNumberOfRanks(ranks) => write!(f, "{} ranks instead of 8", ranks),
+            FenError::InvalidPiece(ch) => write!(f, "Invalid piece character: {}", ch),
+            FenError::WrongRankLength(rank) => write!(f, "Rank {} is not 8 squares long", rank),
+            FenError::MissingKing(color) => write!(f, "Missing {:?} king", color),
+            FenError::TooManyKings(color) => write!(f, "More than one {:?} king", color),
+            FenError::PawnOnBackRank(square) => {
+                write!(f, "Pawn on a back rank: {}", square_name(*square))
+            }
+            FenError::InvalidTurn(turn) => write!(f, "Invalid side to move: {}", turn),
+            FenError::InvalidCastling(castling) => {
+                write!(f, "Invalid castling field: {}", castling)
+            }
+            FenError::InconsistentCastlingRights(right) => {
+                write!(f, "No king and rook for the castling right {}", right)
+            }
+            FenError::ImpossibleEnPassant(square) => {
+                write!(f, "Impossible en passant square: {}", square)
+            }
+            FenError::InvalidMoveCounter(counter) => write!(f, "Invalid move counter: {}", counter),
+            FenError::OpponentInCheck => write!(f, "The side not to move is in check"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Position {
     white_board: u64,
     black_board: u64,
@@ -40,17 +89,40 @@ pub struct Position {
     kings_board: u64,
 
     turn: PieceColor,
+    // Moves since the last capture or pawn move, and number of the full move starting at 1
+    half_move_clock: u16,
     number_of_move: u16,
     castling_rights: u8, // 0 0 0 0 0(q) 0(k) 0(Q) 0(K)
+    // Square of the rook of each castling right, in the order of the bits above,
+    // and the castling rights kept when a piece leaves or lands on a square
+    castling_rooks: [i8; 4],
+    castling_rights_masks: [u8; 64],
+    // Castling is written as the king capturing its own rook, as required by Chess960 GUIs
+    chess960: bool,
 
     // En passant square
     en_passant: Option<i8>,
+
+    // Zobrist key, updated incrementally with each move
+    key: u64,
+    // Pieces giving check to the side to move, and for each color the pieces of both colors
+    // standing alone between its king and an opponent slider, updated with each move
+    checkers: u64,
+    blockers_for_king: [u64; 2],
+    // Keys of the positions before each move played, for the detection of repetitions
+    key_history: Vec<u64>,
 }
 
 impl Position {
+    // Convenience for FENs known to be valid, such as constants and tests
     pub fn from_fen(fen: &str) -> Position {
-        let mut board_index: usize = 56;
+        match Position::try_from_fen(fen) {
+            Ok(position) => position,
+            Err(error) => panic!("{}: {}", error, fen),
+        }
+    }
 
+    pub fn try_from_fen(fen: &str) -> Result<Position, FenError> {
         let mut white_board: u64 = 0;
         let mut black_board: u64 = 0;
         let mut pawns_board: u64 = 0;
@@ -61,119 +133,147 @@ impl Position {
         let mut kings_board: u64 = 0;
 
         let mut parts = fen.split_whitespace();
-        let board_part = parts.next().expect("Missing board part");
-        let turn_part = parts.next().expect("Missing turn part");
+        let board_part = parts.next().ok_or(FenError::MissingField("board"))?;
+        let turn_part = parts.next().ok_or(FenError::MissingField("turn"))?;
         let castling_part = parts.next().unwrap_or("-");
         let en_passant_part = parts.next().unwrap_or("-");
-        let half_move_part = parts.next().expect("Missing half move part");
-        let number_of_moves_move_part = parts.next().expect("Missing number of moves part");
+        // The move counters are often left out by GUIs
+        let half_move_part = parts.next().unwrap_or("0");
+        let number_of_moves_move_part = parts.next().unwrap_or("1");
 
-        for ch in board_part.chars() {
-            match ch {
-                '/' => {
-                    board_index = board_index - 16;
+        let ranks: Vec<&str> = board_part.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongNumberOfRanks(ranks.len()));
+        }
+        for (rank, rank_part) in (0..8).rev().zip(ranks) {
+            let mut file = 0;
+            for ch in rank_part.chars() {
+                if let Some(skip) = ch.to_digit(10).filter(|skip| (1..=8).contains(skip)) {
+                    file += skip as usize;
                     continue;
                 }
-
-                '1'..='8' => {
-                    let skip = ch.to_digit(10).unwrap() - 1;
-                    board_index += skip as usize;
-                }
-
-                'P' | 'p' => {
-                    pawns_board |= 1u64 << board_index;
-                    if ch == 'P' {
-                        white_board |= 1u64 << board_index;
-                    } else {
-                        black_board |= 1u64 << board_index;
-                    }
-                }
-
-                'N' | 'n' => {
-                    knights_board |= 1u64 << board_index;
-                    if ch == 'N' {
-                        white_board |= 1u64 << board_index;
-                    } else {
-                        black_board |= 1u64 << board_index;
-                    }
-                }
-
-                'B' | 'b' => {
-                    bishops_board |= 1u64 << board_index;
-                    if ch == 'B' {
-                        white_board |= 1u64 << board_index;
-                    } else {
-                        black_board |= 1u64 << board_index;
-                    }
-                }
-
-                'R' | 'r' => {
-                    rooks_board |= 1u64 << board_index;
-                    if ch == 'R' {
-                        white_board |= 1u64 << board_index;
-                    } else {
-                        black_board |= 1u64 << board_index;
-                    }
-                }
-
-                'Q' | 'q' => {
-                    queens_board |= 1u64 << board_index;
-                    if ch == 'Q' {
-                        white_board |= 1u64 << board_index;
-                    } else {
-                        black_board |= 1u64 << board_index;
-                    }
-                }
-
-                'K' | 'k' => {
-                    kings_board |= 1u64 << board_index;
-                    if ch == 'K' {
-                        white_board |= 1u64 << board_index;
+                let board = match ch.to_ascii_lowercase() {
+                    'p' => &mut pawns_board,
+                    'n' => &mut knights_board,
+                    'b' => &mut bishops_board,
+                    'r' => &mut rooks_board,
+                    'q' => &mut queens_board,
+                    'k' => &mut kings_board,
+                    _ => return Err(FenError::InvalidPiece(ch)),
+                };
+                if file < 8 {
+                    let mask = 1u64 << (rank * 8 + file);
+                    *board |= mask;
+                    if ch.is_ascii_uppercase() {
+                        white_board |= mask;
                     } else {
-                        black_board |= 1u64 << board_index;
+                        black_board |= mask;
                     }
                 }
-
-                ' ' => break, // End of board part in FEN
-                _ => panic!("Invalid character in FEN: {}", ch),
+                file += 1;
             }
+            if file != 8 {
+                return Err(FenError::WrongRankLength(rank as u8 + 1));
+            }
+        }
 
-            board_index = board_index + 1;
+        for (color, color_board) in [
+            (PieceColor::White, white_board),
+            (PieceColor::Black, black_board),
+        ] {
+            match (kings_board & color_board).count_ones() {
+                0 => return Err(FenError::MissingKing(color)),
+                1 => {}
+                _ => return Err(FenError::TooManyKings(color)),
+            }
+        }
+        let back_ranks_pawns = pawns_board & 0xFF000000000000FF;
+        if back_ranks_pawns != 0 {
+            return Err(FenError::PawnOnBackRank(
+                back_ranks_pawns.trailing_zeros() as i8
+            ));
         }
 
         let turn = match turn_part {
             "w" => PieceColor::White,
             "b" => PieceColor::Black,
-            _ => panic!("Invalid turn character in FEN: {}", turn_part),
+            _ => return Err(FenError::InvalidTurn(turn_part.to_string())),
         };
 
+        // KQkq designate the outermost rook of a side, X-FEN and Shredder-FEN name the file of the rook
         let mut castling_rights: u8 = 0;
+        let mut castling_rooks: [i8; 4] = [7, 0, 63, 56];
+        for ch in castling_part.chars().filter(|ch| *ch != '-') {
+            let (color_board, back_rank) = match ch {
+                'K' | 'Q' | 'A'..='H' => (white_board, 0),
+                'k' | 'q' | 'a'..='h' => (black_board, 56),
+                _ => return Err(FenError::InvalidCastling(castling_part.to_string())),
+            };
+            // The king has to stand on its back rank, with the rook on the side of the right
+            let king_file = match kings_board & color_board & (0xFF << back_rank) {
+                0 => return Err(FenError::InconsistentCastlingRights(ch)),
+                king => (king.trailing_zeros() as i8) - back_rank,
+            };
+            let rooks = ((rooks_board & color_board) >> back_rank) as u8;
+            let rook_file = match ch.to_ascii_lowercase() {
+                'k' => match rooks & !((2u8 << king_file).wrapping_sub(1)) {
+                    0 => return Err(FenError::InconsistentCastlingRights(ch)),
+                    rooks => 7 - rooks.leading_zeros() as i8,
+                },
+                'q' => match rooks & ((1u8 << king_file) - 1) {
+                    0 => return Err(FenError::InconsistentCastlingRights(ch)),
+                    rooks => rooks.trailing_zeros() as i8,
+                },
+                file => (file as u8 - b'a') as i8,
+            };
+            if rooks >> rook_file & 1 == 0 || rook_file == king_file {
+                return Err(FenError::InconsistentCastlingRights(ch));
+            }
+            let right = (rook_file < king_file) as usize + if back_rank == 0 { 0 } else { 2 };
+            castling_rights |= 1 << right;
+            castling_rooks[right] = back_rank + rook_file;
+        }
 
-        if castling_part != "-" {
-            for ch in castling_part.chars() {
-                match ch {
-                    'K' => castling_rights |= 1u8 << 0,
-                    'Q' => castling_rights |= 1u8 << 1,
-                    'k' => castling_rights |= 1u8 << 2,
-                    'q' => castling_rights |= 1u8 << 3,
-                    _ => {}
-                }
+        // Moving the king or the rook of a castling right, or capturing the rook, loses the right
+        let mut castling_rights_masks = [0b1111u8; 64];
+        for (right, rook) in castling_rooks.iter().enumerate() {
+            let color_board = if right < 2 { white_board } else { black_board };
+            if castling_rights >> right & 1 == 1 {
+                castling_rights_masks[*rook as usize] &= !(1 << right);
+                castling_rights_masks[(kings_board & color_board).trailing_zeros() as usize] &=
+                    !(1 << right);
             }
         }
 
-        let mut en_passant_rank: Option<i8> = None;
-        let mut en_passant_file: Option<i8> = None;
-        if en_passant_part != "-" {
-            for ch in en_passant_part.chars() {
-                match ch {
-                    'a'..='z' => en_passant_file = Some((ch as u8 - 'a' as u8) as i8),
-                    '1'..='8' => en_passant_rank = Some((ch.to_digit(10).unwrap() as i8 - 1) * 8),
-                    _ => {}
+        // The square has to be behind a pawn of the opponent that has just been pushed two squares
+        let en_passant = match en_passant_part {
+            "-" => None,
+            _ => {
+                let square = square_from_name(en_passant_part)
+                    .ok_or_else(|| FenError::ImpossibleEnPassant(en_passant_part.to_string()))?;
+                let (rank, pawn, origin, pawn_color_board) = match turn {
+                    PieceColor::White => (5, square - 8, square + 8, black_board),
+                    _ => (2, square + 8, square - 8, white_board),
+                };
+                let occupancy = white_board | black_board;
+                if square / 8 != rank
+                    || pawns_board & pawn_color_board & (1u64 << pawn) == 0
+                    || occupancy & (1u64 << square | 1u64 << origin) != 0
+                {
+                    return Err(FenError::ImpossibleEnPassant(en_passant_part.to_string()));
                 }
+                Some(square)
             }
-        }
+        };
+
+        let half_move_clock = half_move_part
+            .parse()
+            .map_err(|_| FenError::InvalidMoveCounter(half_move_part.to_string()))?;
+        let number_of_move = number_of_moves_move_part
+            .parse()
+            .map_err(|_| FenError::InvalidMoveCounter(number_of_moves_move_part.to_string()))?;
 
-        Position {
+        let mut position = Position {
             white_board,
             black_board,
             pawns_board,
@@ -183,14 +283,25 @@ impl Position {
             queens_board,
             kings_board,
             turn,
-            number_of_move: number_of_moves_move_part.parse().unwrap(),
+            half_move_clock,
+            number_of_move,
             castling_rights,
-            en_passant: if en_passant_rank.is_some() && en_passant_file.is_some() {
-                Some(en_passant_rank.unwrap() + en_passant_file.unwrap())
-            } else {
-                None
-            },
+            castling_rooks,
+            castling_rights_masks,
+            chess960: false,
+            en_passant,
+            key: 0,
+            key_history: Vec::new(),
+            checkers: 0,
+            blockers_for_king: [0; 2],
+        };
+        // The side to move could capture the king
+        if position.is_check(&turn.opposite()) {
+            return Err(FenError::OpponentInCheck);
         }
+        position.key = zobrist_hash::hash_position(&position);
+        position.update_check_info();
+        Ok(position)
     }
 
     pub fn is_legal_move(&self, mov: &Move) -> bool {
@@ -205,25 +316,23 @@ impl Position {
         if source_piece.color == PieceColor::None || source_piece.color != self.turn {
             return false;
         }
-        // The piece at the destination square could not have the same color as the attacker
-        if destination_piece.color == source_piece.color {
+        // The piece at the destination square could not have the same color as the attacker,
+        // except for the castling rook that the king may replace in Chess960
+        if destination_piece.color == source_piece.color
+            && !matches!(mov.move_type, MoveType::ShortCastle | MoveType::LongCastle)
+        {
             return false;
         }
 
         match mov.move_type {
-            MoveType::ShortCastle => {
-                source_piece.piece_type == PieceType::King
-                    && self.can_short_castle(&self.turn)
-                    && (mov.source.abs_diff(mov.destination) == 2)
-            }
-            MoveType::LongCastle => {
+            MoveType::ShortCastle | MoveType::LongCastle => {
                 source_piece.piece_type == PieceType::King
-                    && self.can_long_castle(&self.turn)
-                    && (mov.source.abs_diff(mov.destination) == 2)
+                    && self.can_castle(&self.turn, &mov.move_type)
+                    && mov.destination == castling_targets(&self.turn, &mov.move_type).0
             }
             _ => {
                 // The destination square must appear as one the square that the attacker piece can reach
-                let attacks_squares = generate_mask_moves(&self, &mov.source, &source_piece);
+                let attacks_squares = generate_mask_moves(self, &mov.source, &source_piece);
                 if (attacks_squares & (1u64 << mov.destination)) == 0 {
                     return false;
                 }
@@ -234,239 +343,505 @@ impl Position {
 
     #[inline(always)]
     pub fn is_square_attack_by(&self, index: &i8, attacker_color: &PieceColor) -> bool {
-        let board = self.white_board | self.black_board;
+        self.attackers_to(index, &self.get_board()) & self.get_color_board(attacker_color) != 0
+    }
 
-        let attacker_board = match attacker_color {
-            PieceColor::None => panic!("Invalid color"),
-            PieceColor::White => self.white_board,
-            PieceColor::Black => self.black_board,
-        };
-        let attacker_pawns_board = self.pawns_board & attacker_board;
-        let attacker_knights_board = self.knights_board & attacker_board;
-        let attacker_kings_board = self.kings_board & attacker_board;
+    // The pieces of both colors attacking the square, with the given occupancy for the sliders
+    #[inline(always)]
+    pub fn attackers_to(&self, square: &i8, occupancy: &u64) -> u64 {
+        let table = &lookup_tables::LOOK_UP_TABLE;
+        let index = *square as usize;
+        let diagonal_sliders = self.bishops_board | self.queens_board;
+        let straight_sliders = self.rooks_board | self.queens_board;
+
+        (table.knight_attacks[index] & self.knights_board)
+            | (table.king_attacks[index] & self.kings_board)
+            | (table.white_pawn_attacks[index] & self.pawns_board & self.black_board)
+            | (table.black_pawn_attacks[index] & self.pawns_board & self.white_board)
+            | (generate_move_mask_for_bishop(occupancy, square) & diagonal_sliders)
+            | (generate_move_mask_for_rook(occupancy, square) & straight_sliders)
+    }
 
-        let mut superior_king_mask = lookup_tables::LOOK_UP_TABLE.knight_attacks[*index as usize];
-        if superior_king_mask & attacker_knights_board != 0 {
-            return true;
+    /*
+        Static exchange evaluation: the material won by the move once every capture on its
+        destination square is played, the least valuable attacker first, each side being free
+        to stop capturing. Sliders behind the pieces which capture join the exchange, and pawns
+        reaching the last rank promote to queens. Pins are ignored
+        https://www.chessprogramming.org/Static_Exchange_Evaluation
+    */
+    pub fn see(&self, mov: &Move) -> i32 {
+        if matches!(mov.move_type, MoveType::ShortCastle | MoveType::LongCastle) {
+            return 0;
+        }
+        let destination = mov.destination;
+        let is_promotion_square = (1u64 << destination) & PROMOTION_RANKS != 0;
+        let mut occupancy = self.get_board() ^ (1u64 << mov.source);
+        if mov.move_type == MoveType::EnPassant {
+            occupancy ^= 1u64 << en_passant_capture_square(mov, &self.turn);
         }
 
-        superior_king_mask = lookup_tables::LOOK_UP_TABLE.king_attacks[*index as usize];
-        if superior_king_mask & attacker_kings_board != 0 {
-            return true;
+        // gain[n] is the material won by the side making the nth capture if the exchange stops
+        // after it
+        let mut gain = [0; 32];
+        let mut on_square = self.get_piece_on_square(&mov.source).piece_type as i32;
+        gain[0] = self.get_captured_piece(mov) as i32;
+        if let Some(promotion) = mov.move_type.promotion_piece() {
+            gain[0] += promotion as i32 - PieceType::Pawn as i32;
+            on_square = promotion as i32;
         }
 
-        superior_king_mask = match attacker_color {
-            PieceColor::None => panic!("Invalid color"),
-            PieceColor::White => lookup_tables::LOOK_UP_TABLE.black_pawn_attacks[*index as usize],
-            PieceColor::Black => lookup_tables::LOOK_UP_TABLE.white_pawn_attacks[*index as usize],
-        };
-        if superior_king_mask & attacker_pawns_board != 0 {
-            return true;
+        let mut attackers = self.attackers_to(&destination, &occupancy) & occupancy;
+        let mut side = self.turn.opposite();
+        let mut depth = 0;
+        while depth < gain.len() - 1 {
+            let side_attackers = attackers & self.get_color_board(&side);
+            if side_attackers == 0 {
+                break;
+            }
+            let (square, piece_type) = self.least_valuable_piece(side_attackers);
+            let capture_occupancy = occupancy ^ (1u64 << square);
+            let capture_attackers =
+                self.attackers_to(&destination, &capture_occupancy) & capture_occupancy;
+            // The king cannot capture a defended piece
+            if piece_type == PieceType::King
+                && capture_attackers & self.get_color_board(&side.opposite()) != 0
+            {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = on_square - gain[depth - 1];
+            on_square = piece_type as i32;
+            if piece_type == PieceType::Pawn && is_promotion_square {
+                gain[depth] += PieceType::Queen as i32 - PieceType::Pawn as i32;
+                on_square = PieceType::Queen as i32;
+            }
+            occupancy = capture_occupancy;
+            attackers = capture_attackers;
+            side = side.opposite();
         }
 
-        let superior_bishop_mask = generate_move_mask_for_bishop(&board, &index);
-        if superior_bishop_mask & (self.bishops_board | self.queens_board) & attacker_board != 0 {
-            return true;
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
         }
+        gain[0]
+    }
 
-        let superior_rook_mask = generate_move_mask_for_rook(&board, &index);
-        if superior_rook_mask & (self.rooks_board | self.queens_board) & attacker_board != 0 {
+    // Whether the static exchange evaluation of the move reaches the threshold. The side
+    // to move can win no more than the captured piece and can lose no more than the piece
+    // it moves, and the promotion of the pawn taking it back on the last rank, the exchange
+    // is only resolved between those bounds
+    pub fn see_ge(&self, mov: &Move, threshold: i32) -> bool {
+        if matches!(mov.move_type, MoveType::ShortCastle | MoveType::LongCastle) {
+            return threshold <= 0;
+        }
+        let mut best_case = self.get_captured_piece(mov) as i32;
+        let mut worst_case = best_case - self.get_piece_on_square(&mov.source).piece_type as i32;
+        if let Some(promotion) = mov.move_type.promotion_piece() {
+            best_case += promotion as i32 - PieceType::Pawn as i32;
+            worst_case = best_case - promotion as i32;
+        }
+        if (1u64 << mov.destination) & PROMOTION_RANKS != 0 {
+            worst_case -= PieceType::Queen as i32 - PieceType::Pawn as i32;
+        }
+        if best_case < threshold {
+            return false;
+        }
+        if worst_case >= threshold {
             return true;
         }
+        self.see(mov) >= threshold
+    }
 
-        false
+    #[inline(always)]
+    fn least_valuable_piece(&self, board: u64) -> (i8, PieceType) {
+        let piece_boards = [
+            (self.pawns_board, PieceType::Pawn),
+            (self.knights_board, PieceType::Knight),
+            (self.bishops_board, PieceType::Bishop),
+            (self.rooks_board, PieceType::Rook),
+            (self.queens_board, PieceType::Queen),
+        ];
+        for (piece_board, piece_type) in piece_boards {
+            if board & piece_board != 0 {
+                return ((board & piece_board).trailing_zeros() as i8, piece_type);
+            }
+        }
+        (board.trailing_zeros() as i8, PieceType::King)
     }
 
     #[inline(always)]
     pub fn is_check(&self, color: &PieceColor) -> bool {
+        if *color == self.turn {
+            return self.checkers != 0;
+        }
         self.is_square_attack_by(&self.get_king_coord(color), &color.opposite())
     }
 
     #[inline(always)]
-    pub fn make_move(&mut self, mov: &Move, is_intern_move_request: bool) {
-        // The verification of the origin of the move request helps to avoid the double mask generation well performing move selection:
-        // The function generate_mask in move_generator will be call for move generation and for the verification of the move
-        // That is unnecessary and may slow down the process
-        if !is_intern_move_request && !self.is_legal_move(&mov) {
-            println!("Illegal move {:?}", mov);
-            self.print_board();
-            return;
+    pub fn checkers(&self) -> u64 {
+        self.checkers
+    }
+
+    // The pieces of both colors whose move could uncover a check on the king of the color
+    #[inline(always)]
+    pub fn blockers_for_king(&self, color: &PieceColor) -> u64 {
+        match color {
+            PieceColor::None => 0,
+            PieceColor::White => self.blockers_for_king[0],
+            PieceColor::Black => self.blockers_for_king[1],
         }
+    }
 
+    // The pieces of the color which cannot leave the line between their king and a slider
+    #[inline(always)]
+    pub fn pinned(&self, color: &PieceColor) -> u64 {
+        self.blockers_for_king(color) & self.get_color_board(color)
+    }
+
+    // Whether the move, legal, checks the opponent king, without playing it
+    pub fn gives_check(&self, mov: &Move) -> bool {
+        let table = &lookup_tables::LOOK_UP_TABLE;
+        let color = self.turn;
+        let king = self.get_king_coord(&color.opposite());
+        let own_board = self.get_color_board(&color);
         let source_mask = 1u64 << mov.source;
         let destination_mask = 1u64 << mov.destination;
-        let source_piece = self.get_piece_on_square(&mov.source);
-        let destination_piece = self.get_piece_on_square(&mov.destination);
+        let mut occupancy = (self.get_board() ^ source_mask) | destination_mask;
+        let diagonal_sliders = (self.bishops_board | self.queens_board) & own_board;
+        let mut straight_sliders = (self.rooks_board | self.queens_board) & own_board;
 
-        // Putting 0 at the index of the destination
-        match destination_piece.piece_type {
-            PieceType::Pawn => self.pawns_board &= !destination_mask,
-            PieceType::Knight => self.knights_board &= !destination_mask,
-            PieceType::Bishop => self.bishops_board &= !destination_mask,
-            PieceType::Rook => self.rooks_board &= !destination_mask,
-            PieceType::Queen => self.queens_board &= !destination_mask,
-            PieceType::King => self.kings_board &= !destination_mask,
-            _ => {}
-        }
-
-        // Putting 0 at the index of the source
-        // And moving the piece at the destination by putting 1 at the destination for the corresponding piece
-        match source_piece.piece_type {
-            PieceType::Pawn => {
-                self.pawns_board &= !source_mask;
-                self.pawns_board |= destination_mask;
-            }
-            PieceType::Knight => {
-                self.knights_board &= !source_mask;
-                self.knights_board |= destination_mask;
-            }
-            PieceType::Bishop => {
-                self.bishops_board &= !source_mask;
-                self.bishops_board |= destination_mask;
-            }
-            PieceType::Rook => {
-                self.rooks_board &= !source_mask;
-                self.rooks_board |= destination_mask;
-                match source_piece.color {
-                    PieceColor::None => {}
-                    PieceColor::White => {
-                        if mov.source == 7 {
-                            self.castling_rights &= !(1u8 << 0);
-                        } else if mov.source == 0 {
-                            self.castling_rights &= !(1u8 << 1);
-                        }
-                    }
-                    PieceColor::Black => {
-                        if mov.source == 63 {
-                            self.castling_rights &= !(1u8 << 2);
-                        } else if mov.source == 56 {
-                            self.castling_rights &= !(1u8 << 3);
-                        }
-                    }
+        match mov.move_type {
+            MoveType::ShortCastle | MoveType::LongCastle => {
+                // Only the rook can give check, the king may uncover one in Chess960
+                let (rook_source, rook_destination) = self.castling_rook_squares(mov, &color);
+                occupancy = (self.get_board() ^ source_mask ^ (1u64 << rook_source))
+                    | destination_mask
+                    | (1u64 << rook_destination);
+                straight_sliders =
+                    (straight_sliders ^ (1u64 << rook_source)) | (1u64 << rook_destination);
+            }
+            MoveType::EnPassant => {
+                occupancy ^= 1u64 << en_passant_capture_square(mov, &color);
+                if self.pawn_attacks(&color, mov.destination) & (1u64 << king) != 0 {
+                    return true;
                 }
             }
-            PieceType::Queen => {
-                self.queens_board &= !source_mask;
-                self.queens_board |= destination_mask;
-            }
-            PieceType::King => {
-                self.kings_board &= !source_mask;
-                self.kings_board |= destination_mask;
-                match source_piece.color {
-                    PieceColor::None => {}
-                    PieceColor::White => {
-                        self.castling_rights &= !(1u8 << 0);
-                        self.castling_rights &= !(1u8 << 1);
+            _ => {
+                // Leaving the line between a slider and the king uncovers a check
+                if self.blockers_for_king(&color.opposite()) & source_mask != 0
+                    && table.line[king as usize][mov.source as usize] & destination_mask == 0
+                {
+                    return true;
+                }
+                let piece_type = mov
+                    .move_type
+                    .promotion_piece()
+                    .unwrap_or(self.get_piece_on_square(&mov.source).piece_type);
+                let attacks = match piece_type {
+                    PieceType::Pawn => self.pawn_attacks(&color, mov.destination),
+                    PieceType::Knight => table.knight_attacks[mov.destination as usize],
+                    PieceType::Bishop => {
+                        generate_move_mask_for_bishop(&occupancy, &mov.destination)
                     }
-                    PieceColor::Black => {
-                        self.castling_rights &= !(1u8 << 2);
-                        self.castling_rights &= !(1u8 << 3);
+                    PieceType::Rook => generate_move_mask_for_rook(&occupancy, &mov.destination),
+                    PieceType::Queen => {
+                        generate_move_mask_for_bishop(&occupancy, &mov.destination)
+                            | generate_move_mask_for_rook(&occupancy, &mov.destination)
                     }
-                }
+                    PieceType::King | PieceType::None => 0,
+                };
+                return attacks & (1u64 << king) != 0;
             }
-            _ => {}
         }
 
-        // Updating the boards (for each color)
-        match source_piece.color {
-            PieceColor::White => {
-                self.white_board &= !source_mask;
-                self.white_board |= destination_mask;
+        (generate_move_mask_for_bishop(&occupancy, &king) & diagonal_sliders)
+            | (generate_move_mask_for_rook(&occupancy, &king) & straight_sliders)
+            != 0
+    }
+
+    #[inline(always)]
+    fn pawn_attacks(&self, color: &PieceColor, square: i8) -> u64 {
+        let table = &lookup_tables::LOOK_UP_TABLE;
+        match color {
+            PieceColor::White => table.white_pawn_attacks[square as usize],
+            _ => table.black_pawn_attacks[square as usize],
+        }
+    }
 
-                self.black_board &= !destination_mask;
-            }
-            PieceColor::Black => {
-                self.black_board &= !source_mask;
-                self.black_board |= destination_mask;
+    fn update_check_info(&mut self) {
+        let king = self.get_king_coord(&self.turn);
+        self.checkers = self.attackers_to(&king, &self.get_board())
+            & self.get_color_board(&self.turn.opposite());
+        for (index, color) in [PieceColor::White, PieceColor::Black].iter().enumerate() {
+            let king = self.get_king_coord(color);
+            self.blockers_for_king[index] =
+                self.slider_blockers(&king, &self.get_color_board(&color.opposite()));
+        }
+    }
 
-                self.white_board &= !(destination_mask);
+    // The pieces of both colors standing alone between the square and a slider of the given
+    // color board
+    #[inline(always)]
+    fn slider_blockers(&self, square: &i8, sliders_board: &u64) -> u64 {
+        let table = &lookup_tables::LOOK_UP_TABLE;
+        let board = self.get_board();
+        // The sliders that would attack the square on an empty board
+        let mut snipers = ((generate_move_mask_for_bishop(&0, square)
+            & (self.bishops_board | self.queens_board))
+            | (generate_move_mask_for_rook(&0, square) & (self.rooks_board | self.queens_board)))
+            & sliders_board;
+
+        let mut blockers = 0;
+        while snipers != 0 {
+            let sniper = snipers.trailing_zeros() as usize;
+            snipers &= snipers - 1;
+            let between = table.between[*square as usize][sniper] & board;
+            if between.count_ones() == 1 {
+                blockers |= between;
             }
-            _ => {}
+        }
+        blockers
+    }
+
+    #[inline(always)]
+    pub fn make_move(&mut self, mov: &Move, is_intern_move_request: bool) -> UndoMove {
+        let mut undo_move = UndoMove {
+            source: mov.source,
+            destination: mov.destination,
+            move_type: mov.move_type,
+            piece_moved: PieceType::None,
+            piece_captured: PieceType::None,
+            castling_rights: self.castling_rights,
+            turn: self.turn,
+            en_passant: self.en_passant,
+            half_move_clock: self.half_move_clock,
+            key: self.key,
+            checkers: self.checkers,
+            blockers_for_king: self.blockers_for_king,
         };
 
-        // Applying castling and promotions rules
-        match mov.move_type {
-            MoveType::ShortCastle => match source_piece.color {
-                PieceColor::None => {}
-                PieceColor::White => {
-                    self.rooks_board &= !(1u64 << 7);
-                    self.rooks_board |= 1u64 << 5;
-
-                    self.white_board &= !(1u64 << 7);
-                    self.white_board |= 1u64 << 5;
-                }
-                PieceColor::Black => {
-                    self.rooks_board &= !(1u64 << 63);
-                    self.rooks_board |= 1u64 << 61;
+        // The verification of the origin of the move request helps to avoid the double mask generation well performing move selection:
+        // The function generate_mask in move_generator will be call for move generation and for the verification of the move
+        // That is unnecessary and may slow down the process
+        if !is_intern_move_request && !self.is_legal_move(mov) {
+            println!("Illegal move {:?}", mov);
+            self.print_board();
+            // Nothing has been played, unmaking this move only restores the current state
+            return undo_move;
+        }
 
-                    self.black_board &= !(1u64 << 63);
-                    self.black_board |= 1u64 << 61;
-                }
-            },
-            MoveType::LongCastle => match source_piece.color {
-                PieceColor::None => {}
-                PieceColor::White => {
-                    self.rooks_board &= !(1u64 << 0);
-                    self.rooks_board |= 1u64 << 3;
-
-                    self.white_board &= !(1u64 << 0);
-                    self.white_board |= 1u64 << 3;
-                }
-                PieceColor::Black => {
-                    self.rooks_board &= !(1u64 << 56);
-                    self.rooks_board |= 1u64 << 59;
+        let keys = &*zobrist_hash::ZOBRIST_KEYS;
+        // Taken out before the pieces move, as whether it is hashed depends on the pawns
+        self.key ^= keys.en_passant(&self.get_capturable_en_passant());
 
-                    self.black_board &= !(1u64 << 56);
-                    self.black_board |= 1u64 << 59;
-                }
-            },
-            MoveType::PawnToKnight => {
-                self.pawns_board &= !destination_mask; // Delete the pawn
-                self.knights_board |= destination_mask;
-            }
-            MoveType::PawnToBishop => {
-                self.pawns_board &= !destination_mask; // Delete the pawn
-                self.bishops_board |= destination_mask;
-            }
-            MoveType::PawnToRook => {
-                self.pawns_board &= !destination_mask; // Delete the pawn
-                self.rooks_board |= destination_mask;
-            }
-            MoveType::PawnToQueen => {
-                self.pawns_board &= !destination_mask; // Delete the pawn
-                self.queens_board |= destination_mask;
+        let color = self.turn;
+        let opponent_color = color.opposite();
+        let source_piece = self.get_piece_on_square(&mov.source);
+        let captured_piece = self.get_captured_piece(mov);
+        undo_move.piece_moved = source_piece.piece_type;
+        undo_move.piece_captured = captured_piece;
+
+        match mov.move_type {
+            MoveType::ShortCastle | MoveType::LongCastle => {
+                // The king or the rook may land on the square the other one leaves
+                let (rook_source, rook_destination) = self.castling_rook_squares(mov, &color);
+                self.toggle_piece(mov.source, PieceType::King, color);
+                self.toggle_piece(mov.destination, PieceType::King, color);
+                self.toggle_piece(rook_source, PieceType::Rook, color);
+                self.toggle_piece(rook_destination, PieceType::Rook, color);
             }
             MoveType::EnPassant => {
-                // Updating the boards (for each color)
-                match source_piece.color {
-                    PieceColor::None => {}
-                    PieceColor::White => {
-                        self.pawns_board &= !(1u64 << (mov.destination - 8));
-                        self.black_board &= !(1u64 << (mov.destination - 8));
-                    }
-                    PieceColor::Black => {
-                        self.pawns_board &= !(1u64 << (mov.destination + 8));
-                        self.white_board &= !(1u64 << (mov.destination + 8));
-                    }
-                };
+                self.toggle_piece(
+                    en_passant_capture_square(mov, &color),
+                    PieceType::Pawn,
+                    opponent_color,
+                );
+                self.toggle_piece(mov.source, PieceType::Pawn, color);
+                self.toggle_piece(mov.destination, PieceType::Pawn, color);
+            }
+            _ => {
+                if captured_piece != PieceType::None {
+                    self.toggle_piece(mov.destination, captured_piece, opponent_color);
+                }
+                self.toggle_piece(mov.source, source_piece.piece_type, color);
+                // A promoted pawn lands on the destination as its new piece
+                self.toggle_piece(
+                    mov.destination,
+                    mov.move_type
+                        .promotion_piece()
+                        .unwrap_or(source_piece.piece_type),
+                    color,
+                );
             }
-            _ => {}
         }
 
+        self.key ^= keys.castling(&self.castling_rights);
+
+        // Moving a king or a rook, or capturing a rook on its original square, loses the castling right
+        self.castling_rights &= self.castling_rights_masks[mov.source as usize]
+            & self.castling_rights_masks[mov.destination as usize];
+
         self.en_passant = None;
         if source_piece.piece_type == PieceType::Pawn && mov.source.abs_diff(mov.destination) == 16
         {
-            match source_piece.color {
-                PieceColor::None => {}
-                PieceColor::White => {
-                    self.en_passant = Some(mov.destination - 8);
-                }
-                PieceColor::Black => {
-                    self.en_passant = Some(mov.destination + 8);
+            self.en_passant = Some((mov.source + mov.destination) / 2);
+        }
+        self.turn = opponent_color;
+
+        self.key_history.push(undo_move.key);
+        self.half_move_clock += 1;
+        if source_piece.piece_type == PieceType::Pawn || captured_piece != PieceType::None {
+            self.half_move_clock = 0;
+        }
+        if color == PieceColor::Black {
+            self.number_of_move += 1;
+        }
+
+        self.key ^= keys.castling(&self.castling_rights);
+        self.key ^= keys.en_passant(&self.get_capturable_en_passant());
+        self.key ^= keys.side;
+        debug_assert_eq!(self.key, zobrist_hash::hash_position(self));
+        self.update_check_info();
+
+        undo_move
+    }
+
+    #[inline(always)]
+    pub fn unmake_move(&mut self, undo_move: &UndoMove) {
+        self.turn = undo_move.turn;
+        self.castling_rights = undo_move.castling_rights;
+        self.en_passant = undo_move.en_passant;
+        self.half_move_clock = undo_move.half_move_clock;
+        self.checkers = undo_move.checkers;
+        self.blockers_for_king = undo_move.blockers_for_king;
+
+        // The move has been rejected by make_move, the boards were not touched
+        if undo_move.piece_moved == PieceType::None {
+            return;
+        }
+        if undo_move.turn == PieceColor::Black {
+            self.number_of_move -= 1;
+        }
+        self.key_history.pop();
+
+        let color = undo_move.turn;
+        let opponent_color = color.opposite();
+        let mov = Move {
+            source: undo_move.source,
+            destination: undo_move.destination,
+            move_type: undo_move.move_type,
+            move_score: 0,
+        };
+
+        match undo_move.move_type {
+            MoveType::ShortCastle | MoveType::LongCastle => {
+                let (rook_source, rook_destination) = self.castling_rook_squares(&mov, &color);
+                self.toggle_piece(rook_destination, PieceType::Rook, color);
+                self.toggle_piece(rook_source, PieceType::Rook, color);
+                self.toggle_piece(mov.destination, PieceType::King, color);
+                self.toggle_piece(mov.source, PieceType::King, color);
+            }
+            MoveType::EnPassant => {
+                self.toggle_piece(mov.destination, PieceType::Pawn, color);
+                self.toggle_piece(mov.source, PieceType::Pawn, color);
+                self.toggle_piece(
+                    en_passant_capture_square(&mov, &color),
+                    PieceType::Pawn,
+                    opponent_color,
+                );
+            }
+            _ => {
+                self.toggle_piece(
+                    mov.destination,
+                    mov.move_type
+                        .promotion_piece()
+                        .unwrap_or(undo_move.piece_moved),
+                    color,
+                );
+                self.toggle_piece(mov.source, undo_move.piece_moved, color);
+                if undo_move.piece_captured != PieceType::None {
+                    self.toggle_piece(mov.destination, undo_move.piece_captured, opponent_color);
                 }
             }
         }
+        // The toggles only restore the pieces part of the key
+        self.key = undo_move.key;
+    }
+
+    // Passes the turn, for the null move pruning of the search
+    // The half move clock is reset, so that no repetition is found across the null move
+    pub fn make_null_move(&mut self) -> UndoMove {
+        let undo_move = UndoMove {
+            source: 0,
+            destination: 0,
+            move_type: MoveType::Normal,
+            piece_moved: PieceType::None,
+            piece_captured: PieceType::None,
+            castling_rights: self.castling_rights,
+            turn: self.turn,
+            en_passant: self.en_passant,
+            half_move_clock: self.half_move_clock,
+            key: self.key,
+            checkers: self.checkers,
+            blockers_for_king: self.blockers_for_king,
+        };
+
+        let keys = &*zobrist_hash::ZOBRIST_KEYS;
+        self.key ^= keys.en_passant(&self.get_capturable_en_passant()) ^ keys.side;
+        self.en_passant = None;
         self.turn = self.turn.opposite();
+        self.key_history.push(undo_move.key);
+        self.half_move_clock = 0;
+        debug_assert_eq!(self.key, zobrist_hash::hash_position(self));
+        // No piece moved, the side which passed was not in check and neither is the other one
+        self.checkers = 0;
+
+        undo_move
+    }
+
+    #[inline(always)]
+    pub fn unmake_null_move(&mut self, undo_move: &UndoMove) {
+        self.turn = undo_move.turn;
+        self.checkers = undo_move.checkers;
+        self.en_passant = undo_move.en_passant;
+        self.half_move_clock = undo_move.half_move_clock;
+        self.key = undo_move.key;
+        self.key_history.pop();
+    }
+
+    // Whether the side has pieces other than its king and pawns, without which
+    // zugzwang is likely
+    #[inline(always)]
+    pub fn has_non_pawn_material(&self, color: &PieceColor) -> bool {
+        let color_board = match color {
+            PieceColor::None => 0,
+            PieceColor::White => self.white_board,
+            PieceColor::Black => self.black_board,
+        };
+        color_board & !(self.pawns_board | self.kings_board) != 0
+    }
+
+    // Adds the piece on an empty square or removes it from the square it stands on
+    #[inline(always)]
+    fn toggle_piece(&mut self, index: i8, piece_type: PieceType, color: PieceColor) {
+        let mask = 1u64 << index;
+        match color {
+            PieceColor::None => {}
+            PieceColor::White => self.white_board ^= mask,
+            PieceColor::Black => self.black_board ^= mask,
+        }
+        match piece_type {
+            PieceType::None => {}
+            PieceType::Pawn => self.pawns_board ^= mask,
+            PieceType::Knight => self.knights_board ^= mask,
+            PieceType::Bishop => self.bishops_board ^= mask,
+            PieceType::Rook => self.rooks_board ^= mask,
+            PieceType::Queen => self.queens_board ^= mask,
+            PieceType::King => self.kings_board ^= mask,
+        }
+        self.key ^= zobrist_hash::ZOBRIST_KEYS.piece(&piece_type, &color, &index);
     }
 
     #[inline(always)]
@@ -503,6 +878,15 @@ impl Position {
         Piece { color, piece_type }
     }
 
+    #[inline(always)]
+    pub fn get_captured_piece(&self, mov: &Move) -> PieceType {
+        match mov.move_type {
+            MoveType::EnPassant => PieceType::Pawn,
+            MoveType::ShortCastle | MoveType::LongCastle => PieceType::None,
+            _ => self.get_piece_on_square(&mov.destination).piece_type,
+        }
+    }
+
     pub fn get_available_piece_coords(&self, piece_color: &PieceColor) -> [Option<i8>; 64] {
         let mut coords = [None; 64];
         let mut board = match piece_color {
@@ -548,6 +932,15 @@ impl Position {
         self.black_board
     }
 
+    #[inline(always)]
+    pub fn get_color_board(&self, color: &PieceColor) -> u64 {
+        match color {
+            PieceColor::None => 0,
+            PieceColor::White => self.white_board,
+            PieceColor::Black => self.black_board,
+        }
+    }
+
     #[inline(always)]
     pub fn get_pawns_board(&self) -> u64 {
         self.pawns_board
@@ -572,61 +965,69 @@ impl Position {
     }
 
     #[inline(always)]
-    pub fn can_short_castle(&self, color: &PieceColor) -> bool {
-        let board = self.white_board | self.black_board;
-        let king_index = self.get_king_coord(color);
+    pub fn get_kings_board(&self) -> u64 {
+        self.kings_board
+    }
 
-        match color {
-            PieceColor::None => false,
-            PieceColor::White => {
-                (self.castling_rights >> 0 & 1) == 1
-                    && (self.white_board & self.rooks_board & (1u64 << 7)) != 0
-                    && (board & (1u64 << (king_index + 1))) == 0
-                    && (board & (1u64 << (king_index + 2))) == 0
-                    && !self.is_square_attack_by(&king_index, &PieceColor::Black)
-                    && !self.is_square_attack_by(&(king_index + 1), &PieceColor::Black)
-                    && !self.is_square_attack_by(&(king_index + 2), &PieceColor::Black)
-            }
-            PieceColor::Black => {
-                (self.castling_rights >> 2 & 1) == 1
-                    && (self.black_board & self.rooks_board & (1u64 << 63)) != 0
-                    && (board & (1u64 << (king_index + 1))) == 0
-                    && (board & (1u64 << (king_index + 2))) == 0
-                    && !self.is_square_attack_by(&king_index, &PieceColor::White)
-                    && !self.is_square_attack_by(&(king_index + 1), &PieceColor::White)
-                    && !self.is_square_attack_by(&(king_index + 2), &PieceColor::White)
-            }
-        }
+    #[inline(always)]
+    pub fn can_short_castle(&self, color: &PieceColor) -> bool {
+        self.can_castle(color, &MoveType::ShortCastle)
     }
 
     #[inline(always)]
     pub fn can_long_castle(&self, color: &PieceColor) -> bool {
-        let board = self.white_board | self.black_board;
-        let king_index = self.get_king_coord(color);
+        self.can_castle(color, &MoveType::LongCastle)
+    }
 
-        match color {
-            PieceColor::None => false,
-            PieceColor::White => {
-                (self.castling_rights >> 1 & 1) == 1
-                    && (self.white_board & self.rooks_board & (1u64 << 0)) != 0
-                    && (board & (1u64 << (king_index - 1))) == 0
-                    && (board & (1u64 << (king_index - 2))) == 0
-                    && (board & (1u64 << (king_index - 3))) == 0
-                    && !self.is_square_attack_by(&king_index, &PieceColor::Black)
-                    && !self.is_square_attack_by(&(king_index - 1), &PieceColor::Black)
-                    && !self.is_square_attack_by(&(king_index - 2), &PieceColor::Black)
-            }
-            PieceColor::Black => {
-                (self.castling_rights >> 3 & 1) == 1
-                    && (self.black_board & self.rooks_board & (1u64 << 56)) != 0
-                    && (board & (1u64 << (king_index - 1))) == 0
-                    && (board & (1u64 << (king_index - 2))) == 0
-                    && (board & (1u64 << (king_index - 3))) == 0
-                    && !self.is_square_attack_by(&king_index, &PieceColor::White)
-                    && !self.is_square_attack_by(&(king_index - 1), &PieceColor::White)
-                    && !self.is_square_attack_by(&(king_index - 2), &PieceColor::White)
-            }
+    // The king and the rook go to the g and f files, or to the c and d files, wherever they start from:
+    // the squares they cross must be empty, and the king must not be attacked on its way, the rook
+    // being lifted first since it may hide a slider on the back rank
+    pub fn can_castle(&self, color: &PieceColor, castling_type: &MoveType) -> bool {
+        let right = castling_right_index(color, castling_type);
+        if self.castling_rights >> right & 1 == 0 {
+            return false;
+        }
+
+        let color_board = match color {
+            PieceColor::None => return false,
+            PieceColor::White => self.white_board,
+            PieceColor::Black => self.black_board,
+        };
+        let king = self.get_king_coord(color);
+        let rook = self.castling_rooks[right];
+        if self.rooks_board & color_board & (1u64 << rook) == 0 {
+            return false;
+        }
+
+        let (king_target, rook_target) = castling_targets(color, castling_type);
+        let others = (self.white_board | self.black_board) ^ (1u64 << king) ^ (1u64 << rook);
+        if (rank_span(king, king_target) | rank_span(rook, rook_target)) & others != 0 {
+            return false;
         }
+
+        let occupancy = self.get_board() ^ (1u64 << rook);
+        let opponent_board = self.get_color_board(&color.opposite());
+        (king.min(king_target)..=king.max(king_target))
+            .all(|square| self.attackers_to(&square, &occupancy) & opponent_board == 0)
+    }
+
+    // The square the castling rook comes from and the one it goes to
+    #[inline(always)]
+    pub fn castling_rook_squares(&self, mov: &Move, color: &PieceColor) -> (i8, i8) {
+        let right = castling_right_index(color, &mov.move_type);
+        (
+            self.castling_rooks[right],
+            castling_targets(color, &mov.move_type).1,
+        )
+    }
+
+    #[inline(always)]
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
     }
 
     #[inline(always)]
@@ -634,6 +1035,183 @@ impl Position {
         self.en_passant
     }
 
+    // The en passant square only counts in the key when a pawn of the side to move attacks it,
+    // otherwise the position is the same as without it
+    #[inline(always)]
+    pub fn get_capturable_en_passant(&self) -> Option<i8> {
+        self.en_passant.filter(|square| {
+            self.pawn_attacks(&self.turn.opposite(), *square)
+                & self.pawns_board
+                & self.get_color_board(&self.turn)
+                != 0
+        })
+    }
+
+    #[inline(always)]
+    pub fn get_castling_rights(&self) -> u8 {
+        self.castling_rights
+    }
+
+    #[inline(always)]
+    pub fn get_half_move_clock(&self) -> u16 {
+        self.half_move_clock
+    }
+
+    #[inline(always)]
+    pub fn get_full_move_number(&self) -> u16 {
+        self.number_of_move
+    }
+
+    #[inline(always)]
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    // A position repeated inside the search tree is scored as a draw at once, as the side
+    // to move could repeat it again, while a repetition of the game history has to be threefold
+    pub fn is_repetition(&self, ply: usize) -> bool {
+        let mut repetitions = 0;
+        for distance in self.reversible_distances() {
+            if self.key_history[self.key_history.len() - distance] == self.key {
+                if distance <= ply {
+                    return true;
+                }
+                repetitions += 1;
+                if repetitions == 2 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.reversible_distances()
+            .filter(|distance| self.key_history[self.key_history.len() - distance] == self.key)
+            .count()
+            >= 2
+    }
+
+    // Only the positions with the same side to move since the last capture or pawn move can repeat
+    #[inline(always)]
+    fn reversible_distances(&self) -> impl Iterator<Item = usize> {
+        (4..=(self.half_move_clock as usize).min(self.key_history.len())).step_by(2)
+    }
+
+    // Only a checkmate on the hundredth half move takes precedence over the draw
+    #[inline(always)]
+    pub fn is_fifty_move_draw(&self) -> bool {
+        if self.half_move_clock < 100 {
+            return false;
+        }
+        if !self.is_check(&self.turn) {
+            return true;
+        }
+        let mut moves = MoveList::new();
+        generate_legal_moves(self, &self.turn, &mut moves);
+        !moves.is_empty()
+    }
+
+    // No sequence of legal moves can lead to a checkmate: a lone minor piece,
+    // or bishops all standing on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        if self.pawns_board | self.rooks_board | self.queens_board != 0 {
+            return false;
+        }
+        let minors = self.knights_board | self.bishops_board;
+        if minors.count_ones() <= 1 {
+            return true;
+        }
+        const DARK_SQUARES: u64 = 0xAA55AA55AA55AA55;
+        self.knights_board == 0
+            && (self.bishops_board & DARK_SQUARES == 0 || self.bishops_board & !DARK_SQUARES == 0)
+    }
+
+    #[inline(always)]
+    pub fn is_draw(&self, ply: usize) -> bool {
+        self.is_repetition(ply) || self.is_fifty_move_draw() || self.is_insufficient_material()
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty_squares = 0;
+            for file in 0..8 {
+                let piece = self.get_piece_on_square(&(rank * 8 + file));
+                let ch = match piece.piece_type {
+                    PieceType::Pawn => 'p',
+                    PieceType::Knight => 'n',
+                    PieceType::Bishop => 'b',
+                    PieceType::Rook => 'r',
+                    PieceType::Queen => 'q',
+                    PieceType::King => 'k',
+                    PieceType::None => {
+                        empty_squares += 1;
+                        continue;
+                    }
+                };
+                if empty_squares > 0 {
+                    fen.push_str(&empty_squares.to_string());
+                    empty_squares = 0;
+                }
+                fen.push(match piece.color {
+                    PieceColor::White => ch.to_ascii_uppercase(),
+                    _ => ch,
+                });
+            }
+            if empty_squares > 0 {
+                fen.push_str(&empty_squares.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.turn {
+            PieceColor::Black => " b ",
+            _ => " w ",
+        });
+
+        // KQkq when the king and the rook stand on their standard squares,
+        // the file of the rook otherwise as in Shredder-FEN
+        let mut castling = String::new();
+        for (right, standard) in ['K', 'Q', 'k', 'q'].iter().enumerate() {
+            if self.castling_rights >> right & 1 == 0 {
+                continue;
+            }
+            let color = if right < 2 {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            };
+            let rook = self.castling_rooks[right];
+            let ch = if self.get_king_coord(&color) % 8 == 4 && (rook % 8 == 0 || rook % 8 == 7) {
+                *standard
+            } else {
+                (b'a' + (rook % 8) as u8) as char
+            };
+            castling.push(match color {
+                PieceColor::White => ch.to_ascii_uppercase(),
+                _ => ch,
+            });
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        fen.push_str(&castling);
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&format!(" {}", square_name(square))),
+            None => fen.push_str(" -"),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.half_move_clock, self.number_of_move
+        ));
+        fen
+    }
+
     pub fn print_board(&self) {
         for rank in (0..=7).rev() {
             print!("{} ", rank + 1);
@@ -669,3 +1247,68 @@ impl Position {
         }
     }
 }
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Position, FenError> {
+        Position::try_from_fen(fen)
+    }
+}
+
+pub fn square_name(square: i8) -> String {
+    format!("{}{}", (b'a' + (square % 8) as u8) as char, 1 + square / 8)
+}
+
+pub fn square_from_name(name: &str) -> Option<i8> {
+    match name.as_bytes() {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some(((rank - b'1') * 8 + file - b'a') as i8),
+        _ => None,
+    }
+}
+
+#[inline(always)]
+fn castling_right_index(color: &PieceColor, castling_type: &MoveType) -> usize {
+    let side = match castling_type {
+        MoveType::ShortCastle => 0,
+        _ => 1,
+    };
+    match color {
+        PieceColor::Black => 2 + side,
+        _ => side,
+    }
+}
+
+// The squares where the king and the rook end up after castling
+#[inline(always)]
+pub fn castling_targets(color: &PieceColor, castling_type: &MoveType) -> (i8, i8) {
+    let back_rank = match color {
+        PieceColor::Black => 56,
+        _ => 0,
+    };
+    match castling_type {
+        MoveType::ShortCastle => (back_rank + 6, back_rank + 5),
+        _ => (back_rank + 2, back_rank + 3),
+    }
+}
+
+// The squares from a to b included, both on the same rank
+#[inline(always)]
+fn rank_span(a: i8, b: i8) -> u64 {
+    let (low, high) = (a.min(b), a.max(b));
+    (u64::MAX >> (63 - high)) & (u64::MAX << low)
+}
+
+#[inline(always)]
+fn en_passant_capture_square(mov: &Move, color: &PieceColor) -> i8 {
+    match color {
+        PieceColor::Black => mov.destination + 8,
+        _ => mov.destination - 8,
+    }
+}
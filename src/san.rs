@@ -0,0 +1,249 @@
+use crate::move_list::MoveList;
+use crate::moves_generator::generate_legal_moves;
+use crate::position::{Position, square_from_name, square_name};
+use crate::utils::{Move, MoveType, PieceType};
+use std::fmt;
+
+// https://www.chessprogramming.org/Algebraic_Chess_Notation#Standard_Algebraic_Notation_.28SAN.29
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SanError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "Invalid move notation: {}", san),
+            SanError::IllegalMove(san) => write!(f, "Illegal move: {}", san),
+            SanError::AmbiguousMove(san) => write!(f, "Ambiguous move: {}", san),
+        }
+    }
+}
+
+// The parts of a move written in algebraic notation, the piece is unknown for coordinates like g1f3
+struct SanMove {
+    piece_type: Option<PieceType>,
+    source_file: Option<i8>,
+    source_rank: Option<i8>,
+    destination: i8,
+    promotion: Option<PieceType>,
+}
+
+impl Position {
+    pub fn move_to_san(&self, mov: &Move) -> String {
+        let mut san = match mov.move_type {
+            MoveType::ShortCastle => "O-O".to_string(),
+            MoveType::LongCastle => "O-O-O".to_string(),
+            _ => {
+                let piece_type = self.get_piece_on_square(&mov.source).piece_type;
+                let is_capture = self.get_captured_piece(mov) != PieceType::None
+                    || mov.move_type == MoveType::EnPassant;
+                let mut san = String::new();
+                if piece_type == PieceType::Pawn {
+                    if is_capture {
+                        san.push(file_char(mov.source));
+                    }
+                } else {
+                    san.push(piece_char(&piece_type));
+                    san.push_str(&self.disambiguation(mov, &piece_type));
+                }
+                if is_capture {
+                    san.push('x');
+                }
+                san.push_str(&square_name(mov.destination));
+                if let Some(promotion) = mov.move_type.promotion_piece() {
+                    san.push('=');
+                    san.push(piece_char(&promotion));
+                }
+                san
+            }
+        };
+
+        let mut position = self.clone();
+        position.make_move(mov, true);
+        let turn = position.get_turn();
+        if position.is_check(&turn) {
+            san.push(if legal_moves(&position).is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    // Annotations, missing or superfluous capture signs, long algebraic and coordinate notations,
+    // 0-0 for castling and promotions without the equal sign or to an implied queen are accepted
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+        let moves = legal_moves(self);
+
+        let castling_type = match text.replace('0', "O").to_uppercase().as_str() {
+            "O-O" => Some(MoveType::ShortCastle),
+            "O-O-O" => Some(MoveType::LongCastle),
+            _ => None,
+        };
+        if let Some(castling_type) = castling_type {
+            return moves
+                .into_iter()
+                .find(|mov| mov.move_type == castling_type)
+                .ok_or_else(|| SanError::IllegalMove(san.to_string()));
+        }
+
+        let san_move =
+            parse_san_move(text).ok_or_else(|| SanError::InvalidSyntax(san.to_string()))?;
+        let mut matches = self.matching_moves(&moves, &san_move);
+        // A lowercase b is a file for a pawn move, or a bishop
+        if matches.is_empty()
+            && san_move.piece_type == Some(PieceType::Pawn)
+            && text.starts_with('b')
+            && let Some(bishop_move) = parse_san_move(&format!("B{}", &text[1..]))
+        {
+            matches = self.matching_moves(&moves, &bishop_move);
+        }
+
+        match matches.as_slice() {
+            [] => Err(SanError::IllegalMove(san.to_string())),
+            [mov] => Ok(*mov),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+
+    fn matching_moves(&self, moves: &[Move], san_move: &SanMove) -> Vec<Move> {
+        moves
+            .iter()
+            .filter(|mov| {
+                let piece_type = self.get_piece_on_square(&mov.source).piece_type;
+                // A promotion without its piece is taken as a queen promotion
+                let promotion = match (san_move.promotion, mov.move_type.promotion_piece()) {
+                    (None, Some(_)) => Some(PieceType::Queen),
+                    (promotion, _) => promotion,
+                };
+                mov.destination == san_move.destination
+                    && san_move.piece_type.is_none_or(|p| p == piece_type)
+                    && san_move.source_file.is_none_or(|f| f == mov.source % 8)
+                    && san_move.source_rank.is_none_or(|r| r == mov.source / 8)
+                    && promotion == mov.move_type.promotion_piece()
+            })
+            .copied()
+            .collect()
+    }
+
+    // The file of the source if it tells the moves of the same piece type apart, else its rank,
+    // else the whole square
+    fn disambiguation(&self, mov: &Move, piece_type: &PieceType) -> String {
+        let others: Vec<Move> = legal_moves(self)
+            .into_iter()
+            .filter(|other| {
+                other.destination == mov.destination
+                    && other.source != mov.source
+                    && self.get_piece_on_square(&other.source).piece_type == *piece_type
+            })
+            .collect();
+        if others.is_empty() {
+            String::new()
+        } else if others
+            .iter()
+            .all(|other| other.source % 8 != mov.source % 8)
+        {
+            file_char(mov.source).to_string()
+        } else if others
+            .iter()
+            .all(|other| other.source / 8 != mov.source / 8)
+        {
+            (1 + mov.source / 8).to_string()
+        } else {
+            square_name(mov.source)
+        }
+    }
+}
+
+fn legal_moves(position: &Position) -> Vec<Move> {
+    let turn = position.get_turn();
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &turn, &mut moves);
+    moves.into_iter().collect()
+}
+
+fn parse_san_move(text: &str) -> Option<SanMove> {
+    let mut chars: Vec<char> = text
+        .chars()
+        .filter(|ch| !matches!(ch, 'x' | 'X' | ':' | '-' | '='))
+        .collect();
+
+    let mut promotion = None;
+    if chars.len() > 2 && chars[chars.len() - 2].is_ascii_digit() {
+        promotion = Some(piece_type_from_char(chars.pop()?.to_ascii_uppercase())?);
+    }
+    if chars.len() < 2 {
+        return None;
+    }
+    let destination =
+        square_from_name(&chars.split_off(chars.len() - 2).iter().collect::<String>())?;
+
+    let mut chars = chars.into_iter().peekable();
+    let mut piece_type = match chars.peek().copied() {
+        Some(ch) if ch.is_ascii_uppercase() || matches!(ch, 'n' | 'r' | 'q' | 'k') => {
+            chars.next();
+            Some(piece_type_from_char(ch.to_ascii_uppercase())?)
+        }
+        _ => None,
+    };
+
+    let mut source_file = None;
+    let mut source_rank = None;
+    for ch in chars {
+        match ch {
+            'a'..='h' if source_file.is_none() && source_rank.is_none() => {
+                source_file = Some((ch as u8 - b'a') as i8)
+            }
+            '1'..='8' if source_rank.is_none() => source_rank = Some((ch as u8 - b'1') as i8),
+            _ => return None,
+        }
+    }
+
+    // Without a piece letter, only a whole source square can be the move of a piece
+    if piece_type.is_none() && (source_file.is_none() || source_rank.is_none()) {
+        piece_type = Some(PieceType::Pawn);
+    }
+    if promotion.is_some() && piece_type.is_some_and(|p| p != PieceType::Pawn) {
+        return None;
+    }
+
+    Some(SanMove {
+        piece_type,
+        source_file,
+        source_rank,
+        destination,
+        promotion,
+    })
+}
+
+fn piece_type_from_char(ch: char) -> Option<PieceType> {
+    match ch {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+fn piece_char(piece_type: &PieceType) -> char {
+    match piece_type {
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+        _ => 'P',
+    }
+}
+
+fn file_char(square: i8) -> char {
+    (b'a' + (square % 8) as u8) as char
+}
@@ -1,98 +1,1233 @@
 use crate::evaluation;
-use crate::moves_generator::generate_pseudo_legal_moves;
+use crate::move_list::MoveList;
+use crate::move_picker::{self, HistoryTables, MovePicker};
+use crate::moves_generator::{GenerationMode, generate_legal_moves, generate_moves};
 use crate::position::Position;
-use crate::utils::{Move, PieceColor};
+use crate::time_manager::{DEFAULT_MOVE_OVERHEAD, TimeManager};
+use crate::transposition_table::{Bound, TranspositionTable};
+use crate::uci::move_to_uci;
+use crate::utils::{Move, PieceColor, PieceType};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
 
-pub fn best_move(position: &Position) -> Option<Move> {
-    let moves = generate_pseudo_legal_moves(position, &position.get_turn());
-    let mut best_move = None;
-    let turn = position.get_turn();
-    let mut best_score = -1_000_000 * turn as i32;
-    let depth: usize = 6;
-
-    for mov in moves {
-        match mov {
-            None => break,
-            Some(m) => {
-                let mut temp_position = position.clone();
-                temp_position.make_move(&m, true);
-                if !temp_position.is_check(&turn) {
-                    let score = alpha_beta(&temp_position, depth - 1, -1_000_000, 1_000_000);
-
-                    if score * turn as i32 == 1_000_000 {
-                        best_move = mov;
-                        break;
-                    }
+pub const MAX_DEPTH: usize = 64;
+pub const MAX_PLY: usize = 128;
 
-                    match turn {
-                        PieceColor::White => {
-                            if score > best_score {
-                                best_score = score;
-                                best_move = mov;
-                            }
-                        }
-                        PieceColor::Black => {
-                            if score < best_score {
-                                best_score = score;
-                                best_move = mov;
-                            }
+// Being checkmated at ply p scores -MATE + p, so that shorter mates are preferred
+pub const MATE: i32 = 32_000;
+// Scores beyond this bound are mate scores
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+pub const INFINITY: i32 = MATE + 1;
+
+// Safety margin of the delta pruning in quiescence search
+const DELTA_MARGIN: i32 = 200;
+
+// The move searched at the root is only reported once the search has run for a while
+const CURRMOVE_DELAY: Duration = Duration::from_secs(1);
+
+// The search recurses deeply with large frames, more than the default thread stack in debug builds
+pub const SEARCH_THREAD_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// Centre and scale, in centipawns, of the win rate model of the WDL statistics
+const WDL_SCALE_CENTER: f64 = 180.0;
+const WDL_SCALE: f64 = 60.0;
+
+pub const MIN_ELO: u32 = 600;
+pub const MAX_ELO: u32 = 2400;
+
+// Limits of a search as given by the UCI go command, times are in milliseconds
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u64>,
+    pub movetime: Option<u64>,
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    pub infinite: bool,
+    pub ponder: bool,
+}
+
+// Shared between the thread reading the UCI commands and the search thread
+#[derive(Debug, Default)]
+pub struct SearchSignals {
+    stop: AtomicBool,
+    // While pondering, the search goes on regardless of the time limits
+    ponder: AtomicBool,
+}
+
+impl SearchSignals {
+    pub fn new(ponder: bool) -> SearchSignals {
+        SearchSignals {
+            stop: AtomicBool::new(false),
+            ponder: AtomicBool::new(ponder),
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    // The opponent played the expected move, the search now runs on the clock
+    pub fn ponderhit(&self) {
+        self.ponder.store(false, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.load(Ordering::Relaxed)
+    }
+}
+
+// Settings of the engine, given by the UCI options
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+    pub threads: usize,
+    pub multi_pv: usize,
+    // Time kept aside for the communication with the GUI, in milliseconds
+    pub move_overhead: u64,
+    // When the GUI ponders, part of the time is won back on ponderhits
+    pub ponder: bool,
+    pub show_wdl: bool,
+    // The strength is limited through node and depth limits
+    pub elo: Option<u32>,
+    pub parameters: SearchParameters,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            threads: 1,
+            multi_pv: 1,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            ponder: false,
+            show_wdl: false,
+            elo: None,
+            parameters: SearchParameters::default(),
+        }
+    }
+}
+
+// Switches and tunable values of the selective search, each technique can be turned off
+// to measure what it brings
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchParameters {
+    // Principal variation search: the moves after the first one only have to be proven worse,
+    // with a null window, and are searched again with the full window when they are not
+    // https://www.chessprogramming.org/Principal_Variation_Search
+    pub pvs: bool,
+    // https://www.chessprogramming.org/Null_Move_Pruning
+    pub null_move: bool,
+    pub null_move_min_depth: usize,
+    // The null move is searched with a depth reduced by reduction + depth / depth_divisor
+    pub null_move_reduction: usize,
+    pub null_move_depth_divisor: usize,
+    // Late move reductions of quiet moves, by base + ln(depth) * ln(move number) / divisor
+    // https://www.chessprogramming.org/Late_Move_Reductions
+    pub lmr: bool,
+    pub lmr_min_depth: usize,
+    // The first moves, the most likely to be the best ones, are never reduced
+    pub lmr_min_move_number: usize,
+    pub lmr_base: f64,
+    pub lmr_divisor: f64,
+    // Reverse futility pruning: a node whose static evaluation beats beta by a margin growing
+    // with the depth is expected to fail high
+    // https://www.chessprogramming.org/Reverse_Futility_Pruning
+    pub reverse_futility: bool,
+    pub reverse_futility_max_depth: usize,
+    pub reverse_futility_margin: i32,
+    // Razoring: a node whose static evaluation is below alpha by base + margin * depth * depth
+    // is left to the quiescence search, and pruned when the captures do not bring it back
+    // above alpha. The margin grows fast, as quiet threats are missed by the quiescence search
+    // https://www.chessprogramming.org/Razoring
+    pub razoring: bool,
+    pub razoring_max_depth: usize,
+    pub razoring_base: i32,
+    pub razoring_margin: i32,
+    // Futility pruning: near the leaves, quiet moves cannot raise a static evaluation far below
+    // alpha by more than a margin per remaining ply
+    // https://www.chessprogramming.org/Futility_Pruning
+    pub futility: bool,
+    pub futility_max_depth: usize,
+    pub futility_margin: i32,
+    // Late move pruning: near the leaves, the quiet moves after base + depth * depth moves are
+    // not searched at all
+    // https://www.chessprogramming.org/Futility_Pruning#MoveCountBasedPruning
+    pub lmp: bool,
+    pub lmp_max_depth: usize,
+    pub lmp_base: usize,
+    // Near the leaves, captures losing more than a margin per remaining ply in the static
+    // exchange evaluation are not searched
+    pub see_pruning: bool,
+    pub see_pruning_max_depth: usize,
+    pub see_pruning_margin: i32,
+    // Moves giving check are searched one ply deeper, so that checking sequences are not cut
+    // at the horizon
+    // https://www.chessprogramming.org/Check_Extensions
+    pub check_extension: bool,
+    // Singular extensions: the hash move is searched one ply deeper when a reduced search of
+    // the other moves shows them all worse than its score by a margin per depth. When even
+    // those fail high, multi-cut prunes the node instead
+    // https://www.chessprogramming.org/Singular_Extensions
+    pub singular_extension: bool,
+    pub singular_min_depth: usize,
+    pub singular_margin: i32,
+    pub multi_cut: bool,
+    // Captures of the piece which just captured on the same square
+    // https://www.chessprogramming.org/Recapture_Extensions
+    pub recapture_extension: bool,
+    // Pushes of a pawn to the seventh rank, where it is always passed
+    // https://www.chessprogramming.org/Passed_Pawn_Extensions
+    pub passed_pawn_extension: bool,
+    // The extensions along a line are limited to this many per ply, to avoid search explosions
+    pub extension_budget: f64,
+}
+
+impl Default for SearchParameters {
+    fn default() -> Self {
+        SearchParameters {
+            pvs: true,
+            null_move: true,
+            null_move_min_depth: 3,
+            null_move_reduction: 3,
+            null_move_depth_divisor: 6,
+            lmr: true,
+            lmr_min_depth: 3,
+            lmr_min_move_number: 4,
+            lmr_base: 0.75,
+            lmr_divisor: 2.25,
+            reverse_futility: true,
+            reverse_futility_max_depth: 6,
+            reverse_futility_margin: 80,
+            razoring: true,
+            razoring_max_depth: 3,
+            razoring_base: 250,
+            razoring_margin: 200,
+            futility: true,
+            futility_max_depth: 6,
+            futility_margin: 100,
+            lmp: true,
+            lmp_max_depth: 4,
+            lmp_base: 3,
+            see_pruning: true,
+            see_pruning_max_depth: 4,
+            see_pruning_margin: 100,
+            check_extension: true,
+            singular_extension: true,
+            singular_min_depth: 8,
+            singular_margin: 2,
+            multi_cut: true,
+            recapture_extension: false,
+            passed_pawn_extension: true,
+            extension_budget: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    // The expected reply to the best move, to ponder on
+    pub ponder_move: Option<Move>,
+    pub pv: Vec<Move>,
+    pub score: i32,
+    pub depth: usize,
+    pub nodes: u64,
+}
+
+// Triangular table: the principal variation found at a ply is stored in its row, from the ply on
+// https://www.chessprogramming.org/Triangular_PV-Table
+struct PvTable {
+    moves: Box<[[Option<Move>; MAX_PLY + 1]; MAX_PLY + 1]>,
+    length: [usize; MAX_PLY + 1],
+}
+
+impl PvTable {
+    fn new() -> PvTable {
+        PvTable {
+            moves: Box::new([[None; MAX_PLY + 1]; MAX_PLY + 1]),
+            length: [0; MAX_PLY + 1],
+        }
+    }
+
+    #[inline(always)]
+    fn clear(&mut self, ply: usize) {
+        self.length[ply] = ply;
+    }
+
+    // The move followed by the principal variation of the child becomes the one of the ply
+    #[inline(always)]
+    fn update(&mut self, ply: usize, mov: &Move) {
+        let child_length = self.length[ply + 1].max(ply + 1);
+        self.moves[ply][ply] = Some(*mov);
+        for index in ply + 1..child_length {
+            self.moves[ply][index] = self.moves[ply + 1][index];
+        }
+        self.length[ply] = child_length;
+    }
+
+    fn line(&self) -> Vec<Move> {
+        self.moves[0][..self.length[0]]
+            .iter()
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+struct PvLine {
+    score: i32,
+    pv: Vec<Move>,
+}
+
+struct Searcher<'a> {
+    transposition_table: &'a TranspositionTable,
+    signals: &'a SearchSignals,
+    // Helper threads of the lazy SMP only share the transposition table with the main thread,
+    // and report the number of nodes they searched
+    thread_index: usize,
+    helper_nodes: &'a AtomicU64,
+    multi_pv: usize,
+    show_wdl: bool,
+    time_manager: TimeManager,
+    node_limit: Option<u64>,
+    nodes: u64,
+    seldepth: usize,
+    completed_depth: usize,
+    stopped: bool,
+    // The UCI info lines are only printed when searching for a GUI
+    print_info: bool,
+    pv_table: PvTable,
+    history_tables: HistoryTables,
+    // The move played to reach each ply, for the countermove heuristic, None after a null move
+    move_stack: [Option<Move>; MAX_PLY + 1],
+    // Whether that move captured, for the recapture extension
+    capture_stack: [bool; MAX_PLY + 1],
+    // The plies added by extensions along the line to each ply
+    extension_stack: [usize; MAX_PLY + 1],
+    // The hash move left out by the singular extension search of each ply
+    excluded_move_stack: [Option<Move>; MAX_PLY + 1],
+    parameters: SearchParameters,
+    reductions: Box<[[usize; 64]; MAX_DEPTH + 1]>,
+    // No null move is tried before this ply while verifying a null move cutoff
+    null_move_min_ply: usize,
+}
+
+pub fn best_move(
+    position: &mut Position,
+    limits: &SearchLimits,
+    transposition_table: &TranspositionTable,
+) -> Option<Move> {
+    run_search(
+        position,
+        limits,
+        transposition_table,
+        &SearchSignals::default(),
+        &SearchOptions::default(),
+        false,
+    )
+    .best_move
+}
+
+// Searches without printing anything, for tools such as the test suite runner
+pub fn analyse(
+    position: &mut Position,
+    limits: &SearchLimits,
+    transposition_table: &TranspositionTable,
+    options: &SearchOptions,
+) -> SearchResult {
+    run_search(
+        position,
+        limits,
+        transposition_table,
+        &SearchSignals::default(),
+        options,
+        false,
+    )
+}
+
+// Searches for the GUI, reporting the progress with UCI info lines
+pub fn search(
+    position: &mut Position,
+    limits: &SearchLimits,
+    transposition_table: &TranspositionTable,
+    signals: &SearchSignals,
+    options: &SearchOptions,
+) -> SearchResult {
+    run_search(
+        position,
+        limits,
+        transposition_table,
+        signals,
+        options,
+        true,
+    )
+}
+
+// Lazy SMP: the helper threads search the same position, filling the shared transposition table
+// https://www.chessprogramming.org/Lazy_SMP
+fn run_search(
+    position: &mut Position,
+    limits: &SearchLimits,
+    transposition_table: &TranspositionTable,
+    signals: &SearchSignals,
+    options: &SearchOptions,
+    print_info: bool,
+) -> SearchResult {
+    transposition_table.new_search();
+
+    let mut limits = limits.clone();
+    let mut threads = options.threads.max(1);
+    if let Some(elo) = options.elo {
+        let (depth, nodes) = strength_limits(elo);
+        limits.depth = Some(limits.depth.map_or(depth, |d| d.min(depth)));
+        limits.nodes = Some(limits.nodes.map_or(nodes, |n| n.min(nodes)));
+        threads = 1;
+    }
+
+    let helper_signals = SearchSignals::new(false);
+    let helper_nodes = AtomicU64::new(0);
+    let helper_limits = SearchLimits {
+        depth: limits.depth,
+        infinite: true,
+        ..Default::default()
+    };
+
+    thread::scope(|scope| {
+        for thread_index in 1..threads {
+            let mut position = position.clone();
+            let (helper_signals, helper_nodes, helper_limits) =
+                (&helper_signals, &helper_nodes, &helper_limits);
+            thread::Builder::new()
+                .stack_size(SEARCH_THREAD_STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    let mut searcher = Searcher::new(
+                        transposition_table,
+                        helper_signals,
+                        helper_limits,
+                        &position,
+                        options,
+                        thread_index,
+                        helper_nodes,
+                    );
+                    searcher.iterative_deepening(&mut position, helper_limits)
+                })
+                .unwrap();
+        }
+
+        let mut searcher = Searcher::new(
+            transposition_table,
+            signals,
+            &limits,
+            position,
+            options,
+            0,
+            &helper_nodes,
+        );
+        searcher.print_info = print_info;
+        let result = searcher.iterative_deepening(position, &limits);
+        helper_signals.stop();
+        result
+    })
+}
+
+impl<'a> Searcher<'a> {
+    fn new(
+        transposition_table: &'a TranspositionTable,
+        signals: &'a SearchSignals,
+        limits: &SearchLimits,
+        position: &Position,
+        options: &SearchOptions,
+        thread_index: usize,
+        helper_nodes: &'a AtomicU64,
+    ) -> Searcher<'a> {
+        Searcher {
+            transposition_table,
+            signals,
+            thread_index,
+            helper_nodes,
+            multi_pv: if thread_index == 0 {
+                options.multi_pv.max(1)
+            } else {
+                1
+            },
+            show_wdl: options.show_wdl,
+            time_manager: TimeManager::new(limits, &position.get_turn(), options),
+            node_limit: limits.nodes,
+            nodes: 0,
+            seldepth: 0,
+            completed_depth: 0,
+            stopped: false,
+            print_info: false,
+            pv_table: PvTable::new(),
+            history_tables: HistoryTables::new(),
+            move_stack: [None; MAX_PLY + 1],
+            capture_stack: [false; MAX_PLY + 1],
+            extension_stack: [0; MAX_PLY + 1],
+            excluded_move_stack: [None; MAX_PLY + 1],
+            parameters: options.parameters.clone(),
+            reductions: late_move_reductions(&options.parameters),
+            null_move_min_ply: 0,
+        }
+    }
+
+    // Iterative deepening: each iteration fills the transposition table used to order the next one
+    fn iterative_deepening(
+        &mut self,
+        position: &mut Position,
+        limits: &SearchLimits,
+    ) -> SearchResult {
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        let mut result = SearchResult::default();
+
+        // Half of the helpers start one ply deeper, so that the threads do not all search alike
+        let first_depth = (1 + self.thread_index % 2).min(max_depth);
+        for depth in first_depth..=max_depth {
+            self.seldepth = 0;
+
+            // With MultiPV, each line is the best one among the moves not chosen by the previous lines
+            let mut lines: Vec<PvLine> = vec![];
+            let mut excluded_moves = vec![];
+            let mut score = 0;
+            for _ in 0..self.multi_pv {
+                let (line_best_move, line_score) =
+                    self.search_root(position, depth, &excluded_moves);
+                if self.stopped {
+                    break;
+                }
+                match line_best_move {
+                    None => {
+                        if lines.is_empty() {
+                            // No legal move at the root
+                            score = line_score;
                         }
-                        _ => {}
+                        break;
+                    }
+                    Some(mov) => {
+                        excluded_moves.push(mov);
+                        lines.push(PvLine {
+                            score: line_score,
+                            pv: self.pv_table.line(),
+                        });
                     }
                 }
             }
+            if self.stopped {
+                // The aborted iteration is incomplete, the previous one is trusted instead
+                break;
+            }
+
+            lines.sort_by_key(|line| -line.score);
+            self.completed_depth = depth;
+            result.depth = depth;
+            result.best_move = lines.first().map(|line| line.pv[0]);
+            result.pv = lines.first().map_or(vec![], |line| line.pv.clone());
+            result.score = lines.first().map_or(score, |line| line.score);
+            if self.print_info {
+                if lines.is_empty() {
+                    // No legal move at the root, there is only a score to report
+                    println!("info depth 0 score {}", format_score(result.score));
+                } else {
+                    self.print_iteration_info(position, depth, &lines);
+                }
+            }
+
+            // No need to search deeper once a move is forced or a mate within the horizon is found
+            if result.best_move.is_none()
+                || (result.score.abs() >= MATE_BOUND
+                    && (MATE - result.score.abs()) as usize <= depth)
+            {
+                break;
+            }
+            if !self.signals.is_pondering() && !self.time_manager.can_start_iteration() {
+                break;
+            }
         }
+
+        result.nodes = self.nodes + self.helper_nodes.load(Ordering::Relaxed);
+        result.ponder_move = match result.pv.get(1) {
+            Some(reply) => Some(*reply),
+            None => result
+                .best_move
+                .and_then(|m| ponder_move(position, &m, self.transposition_table)),
+        };
+        result
     }
-    best_move
-}
 
-fn alpha_beta(position: &Position, depth: usize, mut alpha: i32, mut beta: i32) -> i32 {
-    if depth == 0 {
-        return evaluation::evaluate(position);
+    fn search_root(
+        &mut self,
+        position: &mut Position,
+        depth: usize,
+        excluded_moves: &[Move],
+    ) -> (Option<Move>, i32) {
+        let hash_move = self
+            .transposition_table
+            .probe(position.key())
+            .and_then(|entry| entry.best_move);
+        let mut move_picker = MovePicker::new(hash_move, self.history_tables.get_killers(0), None);
+
+        let turn = position.get_turn();
+        let mut best_move = None;
+        let mut best_score = -INFINITY;
+        let mut alpha = -INFINITY;
+        let beta = INFINITY;
+        let mut move_number = 0;
+        self.pv_table.clear(0);
+
+        while let Some(mov) = move_picker.next(position, &self.history_tables) {
+            if excluded_moves.iter().any(|m| m.to_u16() == mov.to_u16()) {
+                continue;
+            }
+            let undo_move = position.make_move(&mov, true);
+            if position.is_check(&turn) {
+                position.unmake_move(&undo_move);
+                continue;
+            }
+            move_number += 1;
+            if self.print_info && self.time_manager.elapsed() >= CURRMOVE_DELAY {
+                println!(
+                    "info depth {} currmove {} currmovenumber {}",
+                    depth,
+                    move_to_uci(&mov, position),
+                    move_number + excluded_moves.len()
+                );
+            }
+            self.move_stack[0] = Some(mov);
+            self.capture_stack[0] = undo_move.piece_captured != PieceType::None;
+            let mut score;
+            if move_number == 1 || !self.parameters.pvs {
+                score = -self.alpha_beta(position, depth - 1, 1, -beta, -alpha);
+            } else {
+                score = -self.alpha_beta(position, depth - 1, 1, -alpha - 1, -alpha);
+                if score > alpha && !self.stopped {
+                    score = -self.alpha_beta(position, depth - 1, 1, -beta, -alpha);
+                }
+            }
+            position.unmake_move(&undo_move);
+            if self.stopped {
+                return (best_move, best_score);
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mov);
+                alpha = alpha.max(score);
+                self.pv_table.update(0, &mov);
+            }
+        }
+
+        if best_move.is_none() {
+            // No legal move at the root
+            best_score = if position.is_check(&turn) { -MATE } else { 0 };
+        } else if excluded_moves.is_empty() {
+            self.transposition_table.store(
+                position.key(),
+                best_move,
+                best_score,
+                depth as u8,
+                Bound::Exact,
+            );
+        }
+        (best_move, best_score)
     }
-    let turn = position.get_turn();
-    let moves = generate_pseudo_legal_moves(position, &turn);
-    let mut no_legal_moves = true;
-
-    let mut score = -100_000 * turn as i32;
-    for mov in moves {
-        match mov {
-            None => break,
-            Some(m) => {
-                let mut temp_position = position.clone();
-                temp_position.make_move(&m, true);
-                if !temp_position.is_check(&turn) {
-                    no_legal_moves = false;
-                    let eval = alpha_beta(&temp_position, depth - 1, alpha, beta);
-                    match turn {
-                        PieceColor::White => {
-                            score = score.max(eval);
-                            alpha = alpha.max(score);
-                            if beta <= alpha {
-                                break;
-                            }
-                        }
-                        PieceColor::Black => {
-                            score = score.min(eval);
-                            beta = beta.min(score);
-                            if beta <= alpha {
-                                break;
-                            }
+
+    // Negamax alpha-beta, scores are from the point of view of the side to move
+    fn alpha_beta(
+        &mut self,
+        position: &mut Position,
+        depth: usize,
+        ply: usize,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> i32 {
+        self.pv_table.clear(ply);
+        self.nodes += 1;
+        self.check_limits();
+        if self.stopped {
+            return 0;
+        }
+
+        if position.is_draw(ply) {
+            return 0;
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        // Mate distance pruning: no line from here can do better than mating at the next ply,
+        // nor worse than being mated right now
+        alpha = alpha.max(-MATE + ply as i32);
+        beta = beta.min(MATE - ply as i32 - 1);
+        if alpha >= beta {
+            return alpha;
+        }
+
+        let key = position.key();
+        let original_alpha = alpha;
+        // The search of the other moves than the hash move neither uses nor fills the
+        // transposition table entry of the position
+        let excluded_move = self.excluded_move_stack[ply];
+        let is_pv = beta - alpha > 1;
+        let tt_entry = self.transposition_table.probe(key);
+        let mut hash_move = None;
+        if let Some(entry) = tt_entry {
+            hash_move = entry.best_move;
+            let score = score_from_transposition_table(entry.score, ply);
+            // No cutoff on the principal variation, it would end at this node
+            if !is_pv && excluded_move.is_none() && entry.depth as usize >= depth {
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let turn = position.get_turn();
+        let previous_move = self.move_stack[ply - 1];
+        let in_check = position.is_check(&turn);
+        let can_extend =
+            self.parameters.extension_budget * ply as f64 > self.extension_stack[ply] as f64;
+        // Only needed by the pruning of the nodes outside of the principal variation
+        let static_eval = if in_check || is_pv {
+            -INFINITY
+        } else {
+            evaluation::evaluate_relative(position)
+        };
+
+        if self.parameters.reverse_futility
+            && !is_pv
+            && !in_check
+            && excluded_move.is_none()
+            && depth <= self.parameters.reverse_futility_max_depth
+            && beta.abs() < MATE_BOUND
+            && static_eval - self.parameters.reverse_futility_margin * depth as i32 >= beta
+        {
+            return static_eval;
+        }
+
+        if self.parameters.razoring
+            && !is_pv
+            && !in_check
+            && excluded_move.is_none()
+            && depth <= self.parameters.razoring_max_depth
+            && alpha.abs() < MATE_BOUND
+            && static_eval
+                + self.parameters.razoring_base
+                + self.parameters.razoring_margin * (depth * depth) as i32
+                <= alpha
+        {
+            let score = self.quiescence(position, ply, alpha, alpha + 1);
+            if self.stopped {
+                return 0;
+            }
+            if score <= alpha {
+                return score;
+            }
+        }
+
+        // Null move pruning: when passing the turn still fails high, a move would too, unless
+        // in zugzwang, which is looked for by a verification search when only pawns are left
+        if self.parameters.null_move
+            && !is_pv
+            && !in_check
+            && excluded_move.is_none()
+            && depth >= self.parameters.null_move_min_depth
+            && ply >= self.null_move_min_ply
+            && previous_move.is_some()
+            && static_eval >= beta
+        {
+            let reduction = self.parameters.null_move_reduction
+                + depth / self.parameters.null_move_depth_divisor;
+            let null_depth = depth.saturating_sub(1 + reduction);
+            let undo_move = position.make_null_move();
+            self.move_stack[ply] = None;
+            self.extension_stack[ply + 1] = self.extension_stack[ply];
+            let mut score = -self.alpha_beta(position, null_depth, ply + 1, -beta, -beta + 1);
+            position.unmake_null_move(&undo_move);
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                // A mate found after passing the turn is not a proven one
+                if score >= MATE_BOUND {
+                    score = beta;
+                }
+                if position.has_non_pawn_material(&turn) {
+                    return score;
+                }
+                let null_move_min_ply = self.null_move_min_ply;
+                self.null_move_min_ply = ply + 3 * null_depth / 4 + 1;
+                let verification = self.alpha_beta(position, null_depth, ply, beta - 1, beta);
+                self.null_move_min_ply = null_move_min_ply;
+                if self.stopped {
+                    return 0;
+                }
+                if verification >= beta {
+                    return score;
+                }
+            }
+        }
+
+        let mut move_picker = MovePicker::new(
+            hash_move,
+            self.history_tables.get_killers(ply),
+            self.history_tables.get_counter_move(&previous_move),
+        );
+
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut tried_quiets = MoveList::new();
+        let mut move_number = 0;
+        // The shallow pruning of moves is only done at nodes outside of the principal variation,
+        // and once a move escaping the mate has been found
+        let can_prune = !is_pv && !in_check;
+        while let Some(mov) = move_picker.next(position, &self.history_tables) {
+            let is_hash_move = hash_move.is_some_and(|m| m.to_u16() == mov.to_u16());
+            if is_hash_move && excluded_move.is_some() {
+                continue;
+            }
+            let is_quiet = move_picker::is_quiet(position, &mov);
+            let is_capture = position.get_captured_piece(&mov) != PieceType::None;
+            // Promotions are left to the search, an underpromotion may be the only winning move
+            let is_promotion = mov.move_type.promotion_piece().is_some();
+            let is_losing_capture = can_prune
+                && is_capture
+                && depth <= self.parameters.see_pruning_max_depth
+                && !position.see_ge(&mov, -self.parameters.see_pruning_margin * depth as i32);
+
+            // The hash move is singular when no other move comes close to its score, searched
+            // with a reduced depth and a null window below it
+            let mut extension = 0;
+            if is_hash_move
+                && can_extend
+                && self.parameters.singular_extension
+                && depth >= self.parameters.singular_min_depth
+                && let Some(entry) = tt_entry
+                && matches!(entry.bound, Bound::Lower | Bound::Exact)
+                && entry.depth as usize + 3 >= depth
+                && entry.score.abs() < MATE_BOUND
+            {
+                let singular_beta = entry.score - self.parameters.singular_margin * depth as i32;
+                self.excluded_move_stack[ply] = Some(mov);
+                let score = self.alpha_beta(
+                    position,
+                    (depth - 1) / 2,
+                    ply,
+                    singular_beta - 1,
+                    singular_beta,
+                );
+                self.excluded_move_stack[ply] = None;
+                self.pv_table.clear(ply);
+                if self.stopped {
+                    return 0;
+                }
+                if score < singular_beta {
+                    extension = 1;
+                } else if self.parameters.multi_cut && singular_beta >= beta {
+                    // Several moves beat beta, one of them is expected to hold
+                    return singular_beta;
+                }
+            }
+
+            let gives_check = position.gives_check(&mov);
+
+            if extension == 0 && can_extend {
+                let is_recapture = is_capture
+                    && self.capture_stack[ply - 1]
+                    && previous_move.is_some_and(|m| m.destination == mov.destination);
+                let seventh_rank = match turn {
+                    PieceColor::White => 6,
+                    _ => 1,
+                };
+                let is_passed_pawn_push = position.get_piece_on_square(&mov.source).piece_type
+                    == PieceType::Pawn
+                    && mov.destination / 8 == seventh_rank;
+                if (self.parameters.check_extension && gives_check)
+                    || (self.parameters.recapture_extension && is_recapture)
+                    || (self.parameters.passed_pawn_extension && is_passed_pawn_push)
+                {
+                    extension = 1;
+                }
+            }
+
+            // The pruned moves are not played, but count as tried ones
+            if can_prune
+                && !gives_check
+                && !is_promotion
+                && extension == 0
+                && best_score > -MATE_BOUND
+            {
+                let prune = if !is_capture {
+                    (self.parameters.futility
+                        && depth <= self.parameters.futility_max_depth
+                        && static_eval + self.parameters.futility_margin * depth as i32 <= alpha)
+                        || (self.parameters.lmp
+                            && depth <= self.parameters.lmp_max_depth
+                            && move_number >= self.parameters.lmp_base + depth * depth)
+                } else {
+                    self.parameters.see_pruning && is_losing_capture
+                };
+                if prune {
+                    move_number += 1;
+                    continue;
+                }
+            }
+
+            let undo_move = position.make_move(&mov, true);
+            if position.is_check(&turn) {
+                position.unmake_move(&undo_move);
+                continue;
+            }
+            move_number += 1;
+            self.move_stack[ply] = Some(mov);
+            self.capture_stack[ply] = is_capture;
+            self.extension_stack[ply + 1] = self.extension_stack[ply] + extension;
+
+            // Late quiet moves are searched at a reduced depth first, and at the full depth
+            // only if they turn out better than expected
+            let mut reduction = 0;
+            if self.parameters.lmr
+                && is_quiet
+                && !in_check
+                && !gives_check
+                && extension == 0
+                && depth >= self.parameters.lmr_min_depth
+                && move_number > self.parameters.lmr_min_move_number
+            {
+                reduction = self.reductions[depth.min(MAX_DEPTH)][move_number.min(63)];
+                if is_pv {
+                    reduction = reduction.saturating_sub(1);
+                }
+                reduction = reduction.min(depth.saturating_sub(2));
+            }
+
+            let new_depth = depth - 1 + extension;
+            let mut score;
+            if move_number == 1 {
+                score = -self.alpha_beta(position, new_depth, ply + 1, -beta, -alpha);
+            } else {
+                let scout_beta = if self.parameters.pvs { alpha + 1 } else { beta };
+                score = -self.alpha_beta(
+                    position,
+                    new_depth - reduction,
+                    ply + 1,
+                    -scout_beta,
+                    -alpha,
+                );
+                if reduction > 0 && score > alpha && !self.stopped {
+                    score = -self.alpha_beta(position, new_depth, ply + 1, -scout_beta, -alpha);
+                }
+                if scout_beta < beta && score > alpha && score < beta && !self.stopped {
+                    score = -self.alpha_beta(position, new_depth, ply + 1, -beta, -alpha);
+                }
+            }
+            position.unmake_move(&undo_move);
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mov);
+                if score > alpha {
+                    alpha = score;
+                    self.pv_table.update(ply, &mov);
+                    if alpha >= beta {
+                        if is_quiet {
+                            self.history_tables.update_quiet_cutoff(
+                                &turn,
+                                ply,
+                                depth,
+                                &mov,
+                                &previous_move,
+                                tried_quiets.as_slice(),
+                            );
                         }
-                        _ => {}
+                        break;
                     }
                 }
             }
+            if is_quiet {
+                tried_quiets.push(mov);
+            }
+        }
+
+        if excluded_move.is_some() {
+            return if best_move.is_none() {
+                alpha
+            } else {
+                best_score
+            };
         }
+
+        if best_move.is_none() {
+            return if in_check {
+                -MATE + ply as i32 // Checkmate
+            } else {
+                0 // Stalemate
+            };
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.transposition_table.store(
+            key,
+            best_move,
+            score_to_transposition_table(best_score, ply),
+            depth as u8,
+            bound,
+        );
+
+        best_score
     }
 
-    if no_legal_moves {
-        return if position.is_check(&turn) {
-            -1_000_000 * turn as i32 // Checkmate
+    // Only captures and promotions are searched, unless in check, until the position is quiet
+    // https://www.chessprogramming.org/Quiescence_Search
+    fn quiescence(
+        &mut self,
+        position: &mut Position,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.pv_table.clear(ply);
+        self.seldepth = self.seldepth.max(ply);
+        self.nodes += 1;
+        self.check_limits();
+        if self.stopped {
+            return 0;
+        }
+
+        let turn = position.get_turn();
+        let in_check = position.is_check(&turn);
+        if ply >= MAX_PLY {
+            return if in_check {
+                0
+            } else {
+                evaluation::evaluate_relative(position)
+            };
+        }
+
+        // Standing pat: the side to move is assumed to have at least a quiet move as good as
+        // the static evaluation, which is not true when in check, every evasion has to be tried
+        let mut stand_pat = -INFINITY;
+        let mut best_score = -MATE + ply as i32;
+        if !in_check {
+            stand_pat = evaluation::evaluate_relative(position);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            best_score = stand_pat;
+        }
+
+        let mode = if in_check {
+            GenerationMode::Evasions
         } else {
-            0 // stalemate
+            GenerationMode::Captures
         };
+        let mut moves = MoveList::new();
+        generate_moves(position, &turn, &mode, &mut moves);
+        for mov in moves.iter_mut() {
+            mov.move_score = evaluation::evaluate_move(position, mov);
+        }
+
+        for index in 0..moves.len() {
+            let mov = move_picker::pick_best_move(moves.as_mut_slice(), index);
+
+            // Delta pruning: even winning the captured piece for free would not raise alpha
+            if !in_check && mov.move_type.promotion_piece().is_none() {
+                let gain = position.get_captured_piece(&mov) as i32;
+                if stand_pat + gain + DELTA_MARGIN <= alpha {
+                    continue;
+                }
+            }
+            // Captures losing material are not worth resolving
+            if !in_check && !position.see_ge(&mov, 0) {
+                continue;
+            }
+
+            let undo_move = position.make_move(&mov, true);
+            if position.is_check(&turn) {
+                position.unmake_move(&undo_move);
+                continue;
+            }
+            let score = -self.quiescence(position, ply + 1, -beta, -alpha);
+            position.unmake_move(&undo_move);
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+
+        best_score
+    }
+
+    fn print_iteration_info(&self, position: &Position, depth: usize, lines: &[PvLine]) {
+        let time = self.time_manager.elapsed();
+        let nodes = self.nodes + self.helper_nodes.load(Ordering::Relaxed);
+        let nps = nodes as u128 * 1000 / time.as_millis().max(1);
+        for (index, line) in lines.iter().enumerate() {
+            let wdl = if self.show_wdl {
+                let (win, draw, loss) = win_draw_loss(line.score);
+                format!(" wdl {} {} {}", win, draw, loss)
+            } else {
+                String::new()
+            };
+            let pv: Vec<String> = line.pv.iter().map(|m| move_to_uci(m, position)).collect();
+            println!(
+                "info depth {} seldepth {} multipv {} score {}{} nodes {} nps {} time {} hashfull {} pv {}",
+                depth,
+                self.seldepth.max(depth),
+                index + 1,
+                format_score(line.score),
+                wdl,
+                nodes,
+                nps,
+                time.as_millis(),
+                self.transposition_table.hashfull(),
+                pv.join(" ")
+            );
+        }
     }
 
-    score
+    #[inline(always)]
+    fn check_limits(&mut self) {
+        if self.thread_index > 0 && self.nodes & 2047 == 0 {
+            self.helper_nodes.fetch_add(2048, Ordering::Relaxed);
+        }
+        // The first iteration always completes, so that there is a move to play
+        if self.completed_depth == 0 {
+            return;
+        }
+        if self.node_limit.is_some_and(|limit| self.nodes >= limit) {
+            self.stopped = true;
+        }
+        // Reading the clock is costly, it is only done every few thousand nodes
+        if self.nodes & 2047 == 0
+            && (self.signals.is_stopped()
+                || (!self.signals.is_pondering() && self.time_manager.is_time_over()))
+        {
+            self.stopped = true;
+        }
+    }
+}
+
+// Mate scores are given in moves rather than plies, negative when getting mated,
+// and mate 0 when the side to move is already mated
+pub fn format_score(score: i32) -> String {
+    if score >= MATE_BOUND {
+        format!("mate {}", (MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        format!("mate {}", -((MATE + score) / 2))
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+// Expected results out of 1000 games, from a logistic model of the score
+// https://www.chessprogramming.org/Pawn_Advantage,_Win_Percentage,_and_Elo
+fn win_draw_loss(score: i32) -> (u32, u32, u32) {
+    if score >= MATE_BOUND {
+        return (1000, 0, 0);
+    }
+    if score <= -MATE_BOUND {
+        return (0, 0, 1000);
+    }
+    let win_rate =
+        |score: f64| (1000.0 / (1.0 + ((WDL_SCALE_CENTER - score) / WDL_SCALE).exp())) as u32;
+    let win = win_rate(score as f64);
+    let loss = win_rate(-score as f64);
+    (win, 1000 - win - loss, loss)
+}
+
+// Each 150 Elo doubles the number of nodes, and adds a bit of depth
+fn strength_limits(elo: u32) -> (usize, u64) {
+    let level = elo.clamp(MIN_ELO, MAX_ELO) - MIN_ELO;
+    let depth = 2 + level as usize / 200;
+    let nodes = (200.0 * 2f64.powf(level as f64 / 150.0)) as u64;
+    (depth, nodes)
+}
+
+// The reply stored in the transposition table after the best move, if it is legal
+fn ponder_move(
+    position: &mut Position,
+    best_move: &Move,
+    transposition_table: &TranspositionTable,
+) -> Option<Move> {
+    let turn = position.get_turn();
+    let undo_move = position.make_move(best_move, true);
+    let reply = transposition_table
+        .probe(position.key())
+        .and_then(|entry| entry.best_move)
+        .filter(|reply| {
+            let mut moves = MoveList::new();
+            generate_legal_moves(position, &turn.opposite(), &mut moves);
+            moves.contains(reply)
+        });
+    position.unmake_move(&undo_move);
+    reply
+}
+
+// The reduction of a late move, by depth and by number of the move
+fn late_move_reductions(parameters: &SearchParameters) -> Box<[[usize; 64]; MAX_DEPTH + 1]> {
+    let mut reductions = Box::new([[0; 64]; MAX_DEPTH + 1]);
+    for (depth, row) in reductions.iter_mut().enumerate().skip(1) {
+        for (move_number, reduction) in row.iter_mut().enumerate().skip(1) {
+            *reduction = (parameters.lmr_base
+                + (depth as f64).ln() * (move_number as f64).ln() / parameters.lmr_divisor)
+                as usize;
+        }
+    }
+    reductions
+}
+
+// Mate scores are stored relative to the node rather than to the root,
+// the same position can be reached at different plies
+#[inline(always)]
+fn score_to_transposition_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+#[inline(always)]
+fn score_from_transposition_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
 }
@@ -0,0 +1,97 @@
+use crate::search::{SearchLimits, SearchOptions};
+use crate::utils::PieceColor;
+use std::time::{Duration, Instant};
+
+// https://www.chessprogramming.org/Time_Management
+
+// Moves left in the game assumed when the GUI does not send movestogo
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+// Time kept aside for the communication with the GUI
+pub const DEFAULT_MOVE_OVERHEAD: u64 = 10;
+
+pub struct TimeManager {
+    start: Instant,
+    // Past the soft limit, no new iteration is started
+    soft_limit: Option<Duration>,
+    // Past the hard limit, the running iteration is aborted
+    hard_limit: Option<Duration>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, turn: &PieceColor, options: &SearchOptions) -> TimeManager {
+        let start = Instant::now();
+
+        if limits.infinite {
+            return TimeManager {
+                start,
+                soft_limit: None,
+                hard_limit: None,
+            };
+        }
+
+        if let Some(movetime) = limits.movetime {
+            let limit =
+                Duration::from_millis(movetime.saturating_sub(options.move_overhead).max(1));
+            return TimeManager {
+                start,
+                soft_limit: Some(limit),
+                hard_limit: Some(limit),
+            };
+        }
+
+        let (time, increment) = match turn {
+            PieceColor::Black => (limits.btime, limits.binc.unwrap_or(0)),
+            _ => (limits.wtime, limits.winc.unwrap_or(0)),
+        };
+        let time = match time {
+            None => {
+                return TimeManager {
+                    start,
+                    soft_limit: None,
+                    hard_limit: None,
+                };
+            }
+            Some(time) => time.saturating_sub(options.move_overhead).max(1),
+        };
+
+        let moves_to_go = limits.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, 50);
+        // The share of the remaining time for this move, plus most of the increment
+        let mut soft_limit = time / moves_to_go + increment * 3 / 4;
+        // Pondering saves time on the moves the opponent plays as expected
+        if options.ponder {
+            soft_limit += soft_limit / 4;
+        }
+        let soft_limit = soft_limit.min(time / 2);
+        // The hard limit lets an iteration go well over the soft limit, without risking the clock
+        let hard_limit = (soft_limit * 4).min(time * 3 / 4).max(soft_limit);
+
+        TimeManager {
+            start,
+            soft_limit: Some(Duration::from_millis(soft_limit.max(1))),
+            hard_limit: Some(Duration::from_millis(hard_limit.max(1))),
+        }
+    }
+
+    pub fn get_soft_limit(&self) -> Option<Duration> {
+        self.soft_limit
+    }
+
+    pub fn get_hard_limit(&self) -> Option<Duration> {
+        self.hard_limit
+    }
+
+    #[inline(always)]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[inline(always)]
+    pub fn can_start_iteration(&self) -> bool {
+        self.soft_limit.is_none_or(|limit| self.elapsed() < limit)
+    }
+
+    #[inline(always)]
+    pub fn is_time_over(&self) -> bool {
+        self.hard_limit.is_some_and(|limit| self.elapsed() >= limit)
+    }
+}
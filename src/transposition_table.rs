@@ -0,0 +1,219 @@
+use crate::utils::Move;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+// https://www.chessprogramming.org/Transposition_Table
+// https://www.chessprogramming.org/Shared_Hash_Table#Lockless
+
+pub const DEFAULT_HASH_SIZE_MB: usize = 16;
+const BUCKET_SIZE: usize = 4;
+const AGE_CYCLE: u8 = 64;
+
+#[derive(Clone, Debug, Eq, PartialEq, Copy)]
+#[repr(u8)]
+pub enum Bound {
+    None = 0,
+    Exact = 1,
+    Lower = 2, // The score is at least the stored one (fail high)
+    Upper = 3, // The score is at most the stored one (fail low)
+}
+
+#[derive(Clone, Debug, PartialEq, Copy)]
+pub struct TTEntry {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    pub age: u8,
+}
+
+impl TTEntry {
+    /*
+        Data layout
+        bits  0..16  best move
+        bits 16..48  score
+        bits 48..56  depth
+        bits 56..58  bound
+        bits 58..64  age
+    */
+    #[inline(always)]
+    fn pack(&self) -> u64 {
+        (self.best_move.map_or(0, |m| m.to_u16()) as u64)
+            | ((self.score as u32 as u64) << 16)
+            | ((self.depth as u64) << 48)
+            | ((self.bound as u64) << 56)
+            | (((self.age % AGE_CYCLE) as u64) << 58)
+    }
+
+    #[inline(always)]
+    fn unpack(data: u64) -> TTEntry {
+        TTEntry {
+            best_move: Move::from_u16(data as u16),
+            score: (data >> 16) as u32 as i32,
+            depth: (data >> 48) as u8,
+            bound: match (data >> 56) & 0b11 {
+                1 => Bound::Exact,
+                2 => Bound::Lower,
+                3 => Bound::Upper,
+                _ => Bound::None,
+            },
+            age: (data >> 58) as u8,
+        }
+    }
+}
+
+// The key is stored xored with the data, so an entry torn by a concurrent write fails the verification
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket {
+    slots: [Slot; BUCKET_SIZE],
+}
+
+pub struct TranspositionTable {
+    buckets: Box<[Bucket]>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        TranspositionTable {
+            buckets: allocate_buckets(size_mb),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    pub fn resize(&mut self, size_mb: usize) {
+        self.buckets = allocate_buckets(size_mb);
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for bucket in self.buckets.iter() {
+            for slot in &bucket.slots {
+                slot.key.store(0, Ordering::Relaxed);
+                slot.data.store(0, Ordering::Relaxed);
+            }
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    // Number of entries the table can hold
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    // Must be called before each search, entries of previous searches become the first to be replaced
+    pub fn new_search(&self) {
+        let age = (self.age.load(Ordering::Relaxed) + 1) % AGE_CYCLE;
+        self.age.store(age, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        for slot in &self.bucket(key).slots {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data != 0 && slot.key.load(Ordering::Relaxed) ^ data == key {
+                return Some(TTEntry::unpack(data));
+            }
+        }
+        None
+    }
+
+    #[inline(always)]
+    pub fn store(&self, key: u64, best_move: Option<Move>, score: i32, depth: u8, bound: Bound) {
+        let mut new_entry = TTEntry {
+            best_move,
+            score,
+            depth,
+            bound,
+            age: self.age.load(Ordering::Relaxed),
+        };
+        let bucket = self.bucket(key);
+
+        // Replacement policy: the entry of the same position if any, an empty entry otherwise,
+        // and finally the shallowest entry, entries from older searches counting as shallower
+        let mut replaced = &bucket.slots[0];
+        let mut replaced_worth = i32::MAX;
+        for slot in &bucket.slots {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data == 0 {
+                replaced = slot;
+                break;
+            }
+
+            let entry = TTEntry::unpack(data);
+            if slot.key.load(Ordering::Relaxed) ^ data == key {
+                // Keep a deeper result of the current search unless the new one is exact
+                if bound != Bound::Exact
+                    && entry.age == new_entry.age
+                    && entry.depth > depth.saturating_add(2)
+                {
+                    return;
+                }
+                new_entry.best_move = best_move.or(entry.best_move);
+                write_slot(slot, key, &new_entry);
+                return;
+            }
+
+            let worth = entry.depth as i32 - 8 * age_distance(new_entry.age, entry.age) as i32;
+            if worth < replaced_worth {
+                replaced = slot;
+                replaced_worth = worth;
+            }
+        }
+
+        write_slot(replaced, key, &new_entry);
+    }
+
+    // Per-mille of the table used by the current search, estimated on the first thousand entries
+    pub fn hashfull(&self) -> u16 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sampled_buckets = (1000 / BUCKET_SIZE).min(self.buckets.len());
+        let mut used = 0;
+        for bucket in &self.buckets[..sampled_buckets] {
+            for slot in &bucket.slots {
+                let data = slot.data.load(Ordering::Relaxed);
+                if data != 0 && TTEntry::unpack(data).age == age {
+                    used += 1;
+                }
+            }
+        }
+        (used * 1000 / (sampled_buckets * BUCKET_SIZE)) as u16
+    }
+
+    #[inline(always)]
+    fn bucket(&self, key: u64) -> &Bucket {
+        // The number of buckets is a power of two
+        &self.buckets[key as usize & (self.buckets.len() - 1)]
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_HASH_SIZE_MB)
+    }
+}
+
+#[inline(always)]
+fn write_slot(slot: &Slot, key: u64, entry: &TTEntry) {
+    let data = entry.pack();
+    slot.key.store(key ^ data, Ordering::Relaxed);
+    slot.data.store(data, Ordering::Relaxed);
+}
+
+fn allocate_buckets(size_mb: usize) -> Box<[Bucket]> {
+    let wanted = (size_mb.max(1) * 1024 * 1024) / size_of::<Bucket>();
+    // Largest power of two that fits in the requested size
+    let number_of_buckets = 1usize << (usize::BITS - 1 - wanted.leading_zeros());
+    (0..number_of_buckets).map(|_| Bucket::default()).collect()
+}
+
+#[inline(always)]
+fn age_distance(current_age: u8, entry_age: u8) -> u8 {
+    (AGE_CYCLE + current_age - entry_age) % AGE_CYCLE
+}
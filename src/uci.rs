@@ -1,161 +1,395 @@
-use crate::position::Position;
+use crate::lookup_tables;
+use crate::move_list::MoveList;
+use crate::moves_generator::generate_legal_moves;
+use crate::options::{OptionType, UciOptions};
+use crate::position::{Position, square_name};
 use crate::search;
+use crate::search::{
+    MAX_ELO, MIN_ELO, SEARCH_THREAD_STACK_SIZE, SearchLimits, SearchOptions, SearchParameters,
+    SearchSignals,
+};
+use crate::time_manager::DEFAULT_MOVE_OVERHEAD;
+use crate::transposition_table::{DEFAULT_HASH_SIZE_MB, TranspositionTable};
 use crate::utils::{Move, MoveType, PieceColor};
 use regex::Regex;
 use std::io;
+use std::sync::{Arc, LazyLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
+pub const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+static UCI_MOVE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-h][1-8][a-h][1-8][nbrq]?$").unwrap());
+
+// The search running in the background while the commands keep being read
+struct SearchThread {
+    handle: JoinHandle<()>,
+    signals: Arc<SearchSignals>,
+}
 
 pub fn uci_loop() {
-    let mut position = Position::from_fen("r4r1k/pQ5p/5p2/2p5/2q5/8/PP2nPPP/4RK1R b - - 4 3");
+    // Built now rather than on the clock of the first search
+    LazyLock::force(&lookup_tables::LOOK_UP_TABLE);
+
+    let mut options = engine_options();
+    let mut position = Position::from_fen(STARTPOS);
+    let mut transposition_table = Arc::new(TranspositionTable::default());
+    let mut search_thread: Option<SearchThread> = None;
 
     loop {
-        position.print_board();
         let mut command = String::new();
-        io::stdin().read_line(&mut command).unwrap();
+        // The GUI closed the standard input
+        if io::stdin().read_line(&mut command).unwrap_or(0) == 0 {
+            break;
+        }
         let command = command.trim();
 
-        match command {
-            "uci" => uci_commands(),
+        let command_name = command.split_whitespace().next().unwrap_or("");
+        // The commands changing the position or the options end the search first
+        if matches!(
+            command_name,
+            "stop" | "quit" | "ucinewgame" | "position" | "go" | "setoption"
+        ) {
+            stop_search(&mut search_thread);
+        }
+
+        match command_name {
+            "" => {}
+            "uci" => uci_commands(&options),
             "isready" => println!("readyok"),
-            "ucinewgame" => {}
-            c if c.starts_with("position") => uci_position(command, &mut position),
-            c if c.starts_with("move") => uci_make_move(command, &mut position),
-            c if c.starts_with("go") => go(&mut position),
+            "ucinewgame" => {
+                position = Position::from_fen(STARTPOS);
+                position.set_chess960(options.get_check("UCI_Chess960"));
+                transposition_table.clear();
+            }
+            "setoption" => set_option(
+                command,
+                &mut options,
+                &mut position,
+                &mut transposition_table,
+            ),
+            "position" => uci_position(command, &mut position),
+            "move" => uci_make_move(command, &mut position),
+            "go" => {
+                search_thread = Some(go(
+                    command,
+                    &position,
+                    &transposition_table,
+                    search_options(&options),
+                ))
+            }
+            "ponderhit" => {
+                if let Some(search_thread) = &search_thread {
+                    search_thread.signals.ponderhit();
+                }
+            }
+            "d" => {
+                position.print_board();
+                println!("Fen: {}", position);
+            }
             "stop" => {}
             "quit" => break,
-            _ => println!("Command not found {}", command),
+            // Unknown commands are ignored, as required by the protocol
+            _ => {}
         }
     }
-}
-
-fn uci_commands() {
-    println!("\nid name {}", "Zeno");
-    println!("id author {}\n", "Toudonou");
 
-    println!("Available UCI commands:");
-    println!("\t * uci");
-    println!("\t * isready");
-    println!("\t * ucinewgame");
+    stop_search(&mut search_thread);
+}
 
-    println!("\t * position");
-    println!("\t\t * fen <FEN>");
-    println!("\t\t * startpos");
+// Waits for the search thread, which answers with its bestmove
+fn stop_search(search_thread: &mut Option<SearchThread>) {
+    if let Some(search_thread) = search_thread.take() {
+        search_thread.signals.stop();
+        search_thread.handle.join().unwrap();
+    }
+}
 
-    println!("\t * go");
-    println!("\t\t * wtime <MILLISECONDS>\n\t* btime <MILLISECONDS>");
-    println!("\t\t * winc <MILLISECONDS>\n\t* binc <MILLISECONDS>");
-    println!(
-        "\t\t * movestogo <INTEGER>\n\t* depth <INTEGER>\n\t* nodes <INTEGER>\n\t* movetime <MILLISECONDS>"
+pub fn engine_options() -> UciOptions {
+    let mut options = UciOptions::new();
+    options.add(
+        "Hash",
+        OptionType::Spin {
+            default: DEFAULT_HASH_SIZE_MB as i64,
+            min: 1,
+            max: 65536,
+        },
     );
-    println!("\t\t * infinite");
-
-    println!("\t * stop");
-    println!("\t * quit\n");
-    println!("uciok\n");
+    options.add("Clear Hash", OptionType::Button);
+    options.add(
+        "Threads",
+        OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 256,
+        },
+    );
+    options.add(
+        "MultiPV",
+        OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 256,
+        },
+    );
+    options.add("Ponder", OptionType::Check { default: false });
+    options.add(
+        "Move Overhead",
+        OptionType::Spin {
+            default: DEFAULT_MOVE_OVERHEAD as i64,
+            min: 0,
+            max: 5000,
+        },
+    );
+    options.add("UCI_Chess960", OptionType::Check { default: false });
+    options.add("UCI_ShowWDL", OptionType::Check { default: false });
+    options.add("UCI_LimitStrength", OptionType::Check { default: false });
+    options.add(
+        "UCI_Elo",
+        OptionType::Spin {
+            default: 1500,
+            min: MIN_ELO as i64,
+            max: MAX_ELO as i64,
+        },
+    );
+    options
 }
 
-pub fn uci_make_move(command: &str, position: &mut Position) {
-    // if command.starts_with("") {
-    let moves = command[5usize..].split_whitespace();
+// The options read by the search are taken at the start of each one
+pub fn search_options(options: &UciOptions) -> SearchOptions {
+    SearchOptions {
+        threads: options.get_spin("Threads") as usize,
+        multi_pv: options.get_spin("MultiPV") as usize,
+        move_overhead: options.get_spin("Move Overhead") as u64,
+        ponder: options.get_check("Ponder"),
+        show_wdl: options.get_check("UCI_ShowWDL"),
+        elo: options
+            .get_check("UCI_LimitStrength")
+            .then(|| options.get_spin("UCI_Elo") as u32),
+        parameters: SearchParameters::default(),
+    }
+}
 
-    moves.for_each(|move_string| position.make_move(&uci_move(move_string, position), false))
-    // }
+fn uci_commands(options: &UciOptions) {
+    println!("id name Zeno");
+    println!("id author Toudonou");
+    for option in options.iter() {
+        println!("{}", option);
+    }
+    println!("uciok");
 }
 
-fn uci_position(command: &str, position: &mut Position) {
-    // if command.starts_with("position fen") {
-    //     *position = Position::from_fen(&command[13usize..])
-    // }
-    // 
-    // if command.starts_with("position startpos") {
-    //     *position = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
-    // }
-    // 
-    // if command.starts_with("position startpos moves") {
-    //     let moves = command[23usize..].split_whitespace();
-    // 
-    //     moves.for_each(|move_string| position.make_move(&uci_move(move_string, position), false))
-    // }
+// setoption name <id> [value <x>]
+fn set_option(
+    command: &str,
+    options: &mut UciOptions,
+    position: &mut Position,
+    transposition_table: &mut Arc<TranspositionTable>,
+) {
+    let (name, value) = match command.split_once(" value ") {
+        Some((name, value)) => (name, value.trim()),
+        None => (command, ""),
+    };
+    let name = name
+        .trim_start_matches("setoption")
+        .trim()
+        .trim_start_matches("name")
+        .trim();
+
+    match options.set(name, value) {
+        Err(error) => println!("info string {}", error),
+        Ok("Hash") => {
+            let size_mb = options.get_spin("Hash") as usize;
+            // The search thread is over, the table is no longer shared
+            match Arc::get_mut(transposition_table) {
+                Some(table) => table.resize(size_mb),
+                None => *transposition_table = Arc::new(TranspositionTable::new(size_mb)),
+            }
+        }
+        Ok("Clear Hash") => transposition_table.clear(),
+        Ok("UCI_Chess960") => position.set_chess960(options.get_check("UCI_Chess960")),
+        Ok(_) => {}
+    }
 }
 
-fn uci_move(move_string: &str, position: &Position) -> Move {
-    let reg = Regex::new(r"^[a-h][1-8][a-h][1-8][nbrq]?$").unwrap();
-    if !reg.is_match(move_string) {
-        panic!("Incorrect uci move notation");
+// Plays the moves on the current position, a debugging helper outside of the UCI protocol
+pub fn uci_make_move(command: &str, position: &mut Position) {
+    if let Some(new_position) = apply_moves(command.split_whitespace().skip(1), position.clone()) {
+        *position = new_position;
     }
+}
 
-    let part: Vec<char> = move_string.chars().collect();
-    let source_rank = part[1].to_digit(10).unwrap() - 1;
-    let source_file = part[0];
-    let destination_rank = part[3].to_digit(10).unwrap() - 1;
-    let destination_file = part[2];
-    let mut move_type = MoveType::Normal;
+// position [fen <FEN> | startpos] [moves <MOVE>...]
+pub fn uci_position(command: &str, position: &mut Position) {
+    let mut tokens = command.split_whitespace().skip(1);
 
-    if move_string == "e1g1" || move_string == "e1c1" {
-        if position.can_short_castle(&PieceColor::White) && move_string == "e1g1" {
-            move_type = MoveType::ShortCastle;
-        }
-        if position.can_long_castle(&PieceColor::White) && move_string == "e1c1" {
-            move_type = MoveType::LongCastle;
-        }
-    } else if move_string == "e8g8" || move_string == "e8c8" {
-        if position.can_short_castle(&PieceColor::Black) && move_string == "e8g8" {
-            move_type = MoveType::ShortCastle;
-        }
-        if position.can_long_castle(&PieceColor::Black) && move_string == "e8c8" {
-            move_type = MoveType::LongCastle;
+    let mut new_position = match tokens.next() {
+        Some("startpos") => Position::from_fen(STARTPOS),
+        Some("fen") => {
+            let fen: Vec<&str> = tokens.by_ref().take_while(|t| *t != "moves").collect();
+            match Position::try_from_fen(&fen.join(" ")) {
+                Ok(position) => position,
+                Err(error) => {
+                    println!("info string Invalid FEN: {}", error);
+                    return;
+                }
+            }
         }
-    } else if part.len() == 5 {
-        match part[4] {
-            'n' => move_type = MoveType::PawnToKnight,
-            'b' => move_type = MoveType::PawnToBishop,
-            'r' => move_type = MoveType::PawnToRook,
-            'q' => move_type = MoveType::PawnToQueen,
-            _ => {}
+        _ => {
+            println!("info string Invalid position command: {}", command);
+            return;
         }
-    } else if position.get_en_passant().is_some() {
-        if 8 * destination_rank as i8 + (destination_file as u8 - 'a' as u8) as i8
-            == position.get_en_passant().unwrap()
-        {
-            move_type = MoveType::EnPassant;
+    };
+    new_position.set_chess960(position.is_chess960());
+
+    // With a FEN, the moves keyword has already been consumed by take_while
+    let mut tokens = tokens.peekable();
+    if tokens.peek() == Some(&"moves") {
+        tokens.next();
+    }
+    if let Some(new_position) = apply_moves(tokens, new_position) {
+        *position = new_position;
+    }
+}
+
+// The moves are played on the given position, which is dropped at the first illegal one
+fn apply_moves<'a>(
+    moves: impl Iterator<Item = &'a str>,
+    mut position: Position,
+) -> Option<Position> {
+    for move_string in moves {
+        match uci_move(move_string, &position) {
+            Some(mov) => {
+                position.make_move(&mov, true);
+            }
+            None => {
+                println!("info string Illegal move: {}", move_string);
+                return None;
+            }
         }
     }
+    Some(position)
+}
 
-    Move {
-        source: ((source_rank * 8) as u8 + source_file as u8 - 'a' as u8) as i8,
-        destination: ((destination_rank * 8) as u8 + destination_file as u8 - 'a' as u8) as i8,
-        move_type,
-        move_score: 0,
+// Finds the legal move written in coordinate notation, castling is written as the king's move
+pub fn uci_move(move_string: &str, position: &Position) -> Option<Move> {
+    if !UCI_MOVE_REGEX.is_match(move_string) {
+        return None;
     }
+
+    let turn = position.get_turn();
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &turn, &mut moves);
+    moves
+        .into_iter()
+        .find(|mov| move_to_uci(mov, position) == move_string)
 }
 
-fn go(position: &mut Position) {
-    let mov = search::best_move(position);
-    match mov {
-        None => {
-            println!("No move found")
+// Castling is written as the move of the king, or as the king capturing its own rook in Chess960
+// and whenever the king moves less than two squares, where it could be taken for a king step
+pub fn move_to_uci(mov: &Move, position: &Position) -> String {
+    let promotion = match mov.move_type {
+        MoveType::PawnToKnight => "n",
+        MoveType::PawnToBishop => "b",
+        MoveType::PawnToRook => "r",
+        MoveType::PawnToQueen => "q",
+        _ => "",
+    };
+    let destination = match mov.move_type {
+        MoveType::ShortCastle | MoveType::LongCastle
+            if position.is_chess960() || (mov.destination - mov.source).abs() < 2 =>
+        {
+            // Also used for moves deeper in a principal variation, the side is given by the rank
+            let color = if mov.source < 32 {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            };
+            position.castling_rook_squares(mov, &color).0
         }
-        Some(mov) => {
-            let move_type_character: char;
-
-            match mov.move_type {
-                MoveType::ShortCastle => move_type_character = ' ',
-                MoveType::LongCastle => move_type_character = ' ',
-                MoveType::PawnToKnight => move_type_character = 'n',
-                MoveType::PawnToBishop => move_type_character = 'b',
-                MoveType::PawnToRook => move_type_character = 'r',
-                MoveType::PawnToQueen => move_type_character = 'q',
-                _ => move_type_character = ' ',
-            }
+        _ => mov.destination,
+    };
+
+    format!(
+        "{}{}{}",
+        square_name(mov.source),
+        square_name(destination),
+        promotion
+    )
+}
+
+fn go(
+    command: &str,
+    position: &Position,
+    transposition_table: &Arc<TranspositionTable>,
+    options: SearchOptions,
+) -> SearchThread {
+    let limits = parse_go(command);
+    let signals = Arc::new(SearchSignals::new(limits.ponder));
 
-            println!(
-                "bestmove {}{}{}{}{}",
-                ('a' as u8 + (mov.source % 8) as u8) as char,
-                1 + mov.source / 8,
-                ('a' as u8 + (mov.destination % 8) as u8) as char,
-                1 + mov.destination / 8,
-                move_type_character
+    let root = position.clone();
+    let mut position = position.clone();
+    let transposition_table = Arc::clone(transposition_table);
+    let thread_signals = Arc::clone(&signals);
+    let handle = thread::Builder::new()
+        .stack_size(SEARCH_THREAD_STACK_SIZE)
+        .spawn(move || {
+            let result = search::search(
+                &mut position,
+                &limits,
+                &transposition_table,
+                &thread_signals,
+                &options,
             );
+
+            // The bestmove of an infinite or ponder search is only sent once the GUI asks for it
+            while (limits.infinite || thread_signals.is_pondering()) && !thread_signals.is_stopped()
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            match (result.best_move, result.ponder_move) {
+                // No legal move, the GUI expects a null move
+                (None, _) => println!("bestmove 0000"),
+                (Some(best_move), None) => println!("bestmove {}", move_to_uci(&best_move, &root)),
+                (Some(best_move), Some(ponder_move)) => println!(
+                    "bestmove {} ponder {}",
+                    move_to_uci(&best_move, &root),
+                    move_to_uci(&ponder_move, &root)
+                ),
+            }
+        })
+        .unwrap();
+
+    SearchThread { handle, signals }
+}
+
+fn parse_go(command: &str) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut tokens = command.split_whitespace().skip(1);
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "infinite" => limits.infinite = true,
+            "ponder" => limits.ponder = true,
+            "wtime" => limits.wtime = parse_next(&mut tokens),
+            "btime" => limits.btime = parse_next(&mut tokens),
+            "winc" => limits.winc = parse_next(&mut tokens),
+            "binc" => limits.binc = parse_next(&mut tokens),
+            "movestogo" => limits.movestogo = parse_next(&mut tokens),
+            "movetime" => limits.movetime = parse_next(&mut tokens),
+            "depth" => limits.depth = parse_next(&mut tokens),
+            "nodes" => limits.nodes = parse_next(&mut tokens),
+            _ => {}
         }
     }
+    limits
+}
+
+fn parse_next<'a, T: std::str::FromStr>(tokens: &mut impl Iterator<Item = &'a str>) -> Option<T> {
+    let value = tokens.next()?;
+    // GUIs may send negative times when the clock has run out
+    let value = if value.starts_with('-') { "0" } else { value };
+    value.parse().ok()
 }
@@ -48,6 +48,19 @@ pub enum MoveType {
     PawnToQueen,
     EnPassant,
 }
+
+impl MoveType {
+    #[inline(always)]
+    pub fn promotion_piece(&self) -> Option<PieceType> {
+        match self {
+            MoveType::PawnToKnight => Some(PieceType::Knight),
+            MoveType::PawnToBishop => Some(PieceType::Bishop),
+            MoveType::PawnToRook => Some(PieceType::Rook),
+            MoveType::PawnToQueen => Some(PieceType::Queen),
+            _ => None,
+        }
+    }
+}
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub struct Move {
     pub source: i8,
@@ -56,6 +69,38 @@ pub struct Move {
     pub move_score: i32,
 }
 
+impl Move {
+    // Packs the move on 16 bits: source (6 bits), destination (6 bits) and move type (3 bits)
+    #[inline(always)]
+    pub fn to_u16(&self) -> u16 {
+        (self.source as u16) | ((self.destination as u16) << 6) | ((self.move_type as u16) << 12)
+    }
+
+    // A packed value of 0 (a1a1) is never a move
+    #[inline(always)]
+    pub fn from_u16(packed: u16) -> Option<Move> {
+        if packed == 0 {
+            return None;
+        }
+        let move_type = match packed >> 12 {
+            0 => MoveType::Normal,
+            1 => MoveType::ShortCastle,
+            2 => MoveType::LongCastle,
+            3 => MoveType::PawnToKnight,
+            4 => MoveType::PawnToBishop,
+            5 => MoveType::PawnToRook,
+            6 => MoveType::PawnToQueen,
+            _ => MoveType::EnPassant,
+        };
+        Some(Move {
+            source: (packed & 0x3F) as i8,
+            destination: ((packed >> 6) & 0x3F) as i8,
+            move_type,
+            move_score: 0,
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub struct UndoMove {
     pub source: i8,
@@ -66,6 +111,10 @@ pub struct UndoMove {
     pub castling_rights: u8,
     pub turn: PieceColor,
     pub en_passant: Option<i8>,
+    pub half_move_clock: u16,
+    pub key: u64,
+    pub checkers: u64,
+    pub blockers_for_king: [u64; 2],
 }
 
 pub fn count_set_bit(value: u64) -> u8 {
@@ -91,4 +140,3 @@ pub fn random_u64() -> u64 {
 pub fn random_u64_few_bits() -> u64 {
     random_u64() & random_u64() & random_u64()
 }
-
@@ -1,78 +1,109 @@
-use crate::utils::{Piece, PieceColor, PieceType};
-use std::collections::HashMap;
-use rand::Rng;
 use crate::position::Position;
+use crate::utils::{PieceColor, PieceType};
+use std::sync::LazyLock;
 
-pub struct ZobristHash {
-    keys_tables: HashMap<Piece, Vec<u64>>,
-    hashs: HashMap<u64, f64>
-}
-
-impl ZobristHash {
-    pub fn new() -> ZobristHash{
-        let mut keys_tables: HashMap<Piece, Vec<u64>> = HashMap::new();
-        let hashs: HashMap<u64, f64> = HashMap::new();
+pub static ZOBRIST_KEYS: LazyLock<ZobristKeys> = LazyLock::new(ZobristKeys::init);
 
-        keys_tables.insert(Piece{color:PieceColor::None, piece_type:  PieceType::None}, Vec::new());
+// The seed is fixed so that the keys, and every key derived from them, are the same between runs
+const ZOBRIST_SEED: u64 = 0x5A45_4E4F_5A45_4E4F;
 
-        keys_tables.insert(Piece{color:PieceColor::White, piece_type:  PieceType::  Pawn}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::White, piece_type:  PieceType::  Knight}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::White, piece_type:  PieceType::  Bishop}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::White, piece_type:  PieceType::  Rook}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::White, piece_type:  PieceType::  Queen}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::White, piece_type:  PieceType::  King}, Vec::new());
+pub struct ZobristKeys {
+    pub pieces: [[[u64; 64]; 6]; 2],
+    pub side: u64,
+    pub castling: [u64; 16],
+    pub en_passant_files: [u64; 8],
+}
 
-        keys_tables.insert(Piece{color:PieceColor::Black, piece_type:  PieceType::  Pawn}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::Black, piece_type:  PieceType::  Knight}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::Black, piece_type:  PieceType::  Bishop}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::Black, piece_type:  PieceType::  Rook}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::Black, piece_type:  PieceType::  Queen}, Vec::new());
-        keys_tables.insert(Piece{color:PieceColor::Black, piece_type:  PieceType::  King}, Vec::new());
+impl ZobristKeys {
+    pub fn init() -> ZobristKeys {
+        let mut state = ZOBRIST_SEED;
 
-        let mut rng = rand::rng();
-        for piece_board in &mut keys_tables {
-            for _ in 0..64{
-                piece_board.1.push(rng.random::<u64>());
+        let mut pieces = [[[0u64; 64]; 6]; 2];
+        for color_keys in pieces.iter_mut() {
+            for piece_keys in color_keys.iter_mut() {
+                for key in piece_keys.iter_mut() {
+                    *key = split_mix_64(&mut state);
+                }
             }
         }
+        let side = split_mix_64(&mut state);
+        let mut castling = [0u64; 16];
+        for key in castling.iter_mut() {
+            *key = split_mix_64(&mut state);
+        }
+        let mut en_passant_files = [0u64; 8];
+        for key in en_passant_files.iter_mut() {
+            *key = split_mix_64(&mut state);
+        }
 
-        ZobristHash{
-            keys_tables,
-            hashs
+        ZobristKeys {
+            pieces,
+            side,
+            castling,
+            en_passant_files,
         }
     }
 
-    pub fn hash_position(&self, position:& Position) -> u64{
-        let mut hash: u64 = 0;
-
-        for rank in 0..=7 {
-            for file in 'a'..='h' {
-                let index = (rank * 8 + file as usize) as i8;
-                let piece = position.get_piece_on_square(&index);
-                let i = 8 - rank;
-                let j = file as u8 - 'a' as u8;
-                let piece_hash = self.keys_tables.get(&piece).unwrap()[(8 * i as u8 + j) as usize];
-                hash = hash ^ piece_hash;
-            }
-        }
+    #[inline(always)]
+    pub fn piece(&self, piece_type: &PieceType, color: &PieceColor, index: &i8) -> u64 {
+        let color_index = match color {
+            PieceColor::White => 0,
+            PieceColor::Black => 1,
+            PieceColor::None => return 0,
+        };
+        let piece_index = match piece_type {
+            PieceType::Pawn => 0,
+            PieceType::Knight => 1,
+            PieceType::Bishop => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+            PieceType::None => return 0,
+        };
+        self.pieces[color_index][piece_index][*index as usize]
+    }
 
-        hash
+    #[inline(always)]
+    pub fn castling(&self, castling_rights: &u8) -> u64 {
+        self.castling[(*castling_rights & 0b1111) as usize]
     }
 
-    pub fn contains_position(&self, position: &Position) -> bool{
-        self.hashs.contains_key(&self.hash_position(position))
+    #[inline(always)]
+    pub fn en_passant(&self, en_passant: &Option<i8>) -> u64 {
+        match en_passant {
+            None => 0,
+            Some(square) => self.en_passant_files[(square % 8) as usize],
+        }
     }
+}
+
+// https://prng.di.unimi.it/splitmix64.c
+fn split_mix_64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Computes the key of the position from scratch, Position::key is the incrementally updated equivalent
+pub fn hash_position(position: &Position) -> u64 {
+    let keys = &*ZOBRIST_KEYS;
+    let mut hash: u64 = 0;
 
-    pub fn get_position_evaluation(&self, position: &Position) -> f64{
-        let position_hash = self.hash_position(position);
-        *self.hashs.get(&position_hash).unwrap()
+    let mut board = position.get_board();
+    while board != 0 {
+        let index = board.trailing_zeros() as i8;
+        let piece = position.get_piece_on_square(&index);
+        hash ^= keys.piece(&piece.piece_type, &piece.color, &index);
+        board &= board - 1;
     }
 
-    pub fn insert_position(&mut self, position: &Position, evaluation: &f64){
-        let position_hash = self.hash_position(position);
-        if self.hashs.contains_key(&position_hash){
-            return;
-        }
-        self.hashs.insert(position_hash, *evaluation);
+    if position.get_turn() == PieceColor::Black {
+        hash ^= keys.side;
     }
+    hash ^= keys.castling(&position.get_castling_rights());
+    hash ^= keys.en_passant(&position.get_capturable_en_passant());
+
+    hash
 }
@@ -0,0 +1,89 @@
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_legal_moves;
+use zeno::position::Position;
+use zeno::uci::uci_move;
+use zeno::utils::PieceColor;
+
+const PERFT_SUITE: &str = include_str!("data/perft.epd");
+
+fn legal_moves(position: &Position) -> MoveList {
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &position.get_turn(), &mut moves);
+    moves
+}
+
+// The queries kept up to date by make_move match the ones of the same position set up from
+// scratch, and gives_check matches the check found once the move is played
+fn assert_check_info(depth: i32, position: &mut Position) {
+    let fresh = Position::from_fen(&position.to_fen());
+    assert_eq!(position.checkers(), fresh.checkers(), "{}", position);
+    for color in [PieceColor::White, PieceColor::Black] {
+        assert_eq!(
+            position.blockers_for_king(&color),
+            fresh.blockers_for_king(&color),
+            "{}",
+            position
+        );
+    }
+
+    for mov in legal_moves(position) {
+        let gives_check = position.gives_check(&mov);
+        let undo_move = position.make_move(&mov, true);
+        let turn = position.get_turn();
+        assert_eq!(
+            gives_check,
+            position.checkers() != 0,
+            "{:?} {}",
+            mov,
+            position
+        );
+        assert_eq!(
+            position.checkers() != 0,
+            position.is_square_attack_by(&position.get_king_coord(&turn), &turn.opposite())
+        );
+        if depth > 1 {
+            assert_check_info(depth - 1, position);
+        }
+        position.unmake_move(&undo_move);
+    }
+}
+
+#[test]
+fn check_info_follows_the_moves() {
+    for line in PERFT_SUITE.lines().filter(|line| !line.is_empty()) {
+        let fen = line.split(';').next().unwrap().trim();
+        assert_check_info(3, &mut Position::from_fen(fen));
+    }
+}
+
+#[test]
+fn checkers_and_pinned_pieces() {
+    // Checked by the rook and the knight
+    let position = Position::from_fen("4r1k1/8/8/8/3Q4/3n4/8/4K3 w - - 0 1");
+    assert_eq!(position.checkers(), (1u64 << 60) | (1u64 << 19));
+
+    // The bishop is pinned, nothing stands between the white pieces and the black king
+    let position = Position::from_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1");
+    assert_eq!(position.checkers(), 0);
+    assert_eq!(position.pinned(&PieceColor::White), 1u64 << 12);
+    assert_eq!(position.pinned(&PieceColor::Black), 0);
+    assert_eq!(position.blockers_for_king(&PieceColor::White), 1u64 << 12);
+}
+
+#[test]
+fn moves_giving_check() {
+    let gives_check = |fen: &str, move_string: &str| {
+        let position = Position::from_fen(fen);
+        position.gives_check(&uci_move(move_string, &position).unwrap())
+    };
+    // The rook checks once castled
+    assert!(gives_check("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+    // The bishop uncovers the rook, unless it stays on the file
+    assert!(gives_check("4k3/8/8/8/8/8/4B3/4R1K1 w - - 0 1", "e2d3"));
+    // Taking en passant uncovers the bishop
+    assert!(gives_check("7B/8/8/3pP3/8/2k5/8/4K3 w - d6 0 1", "e5d6"));
+    assert!(!gives_check("7B/8/8/3pP3/8/2k5/8/4K3 w - d6 0 1", "e1e2"));
+    // The new knight checks, the new queen would not
+    assert!(gives_check("8/3P4/4k3/8/8/8/8/4K3 w - - 0 1", "d7d8n"));
+    assert!(!gives_check("8/3P4/4k3/8/8/8/8/4K3 w - - 0 1", "d7d8q"));
+}
@@ -0,0 +1,113 @@
+use zeno::position::Position;
+use zeno::search::{SearchLimits, SearchOptions, SearchSignals, search};
+use zeno::transposition_table::TranspositionTable;
+use zeno::uci::uci_position;
+
+fn position_after(command: &str) -> Position {
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    uci_position(command, &mut position);
+    position
+}
+
+#[test]
+fn draw_by_repetition() {
+    let position = position_after("position startpos moves g1f3 g8f6 f3g1 f6g8");
+    // Twice in the game, only the repetition inside the search tree is a draw
+    assert!(!position.is_repetition(0));
+    assert!(position.is_repetition(4));
+    assert!(!position.is_threefold_repetition());
+
+    let position =
+        position_after("position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8");
+    assert!(position.is_repetition(0));
+    assert!(position.is_threefold_repetition());
+    assert!(position.is_draw(0));
+
+    // The position reached by a pawn move repeats with the side to move
+    let position = position_after("position startpos moves e2e3 g8f6 g1f3 f6g8 f3g1");
+    assert!(!position.is_repetition(0));
+    assert!(position.is_repetition(4));
+    assert!(!position.is_repetition(3));
+    let position = position_after(
+        "position startpos moves e2e3 g8f6 g1f3 f6g8 f3g1 g8f6 g1f3 f6g8 f3g1 g8f6 g1f3 f6g8 f3g1",
+    );
+    assert!(position.is_threefold_repetition());
+}
+
+#[test]
+fn draw_by_the_fifty_move_rule() {
+    let position = position_after("position fen 4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80 moves a1a2");
+    assert_eq!(position.get_half_move_clock(), 100);
+    assert!(position.is_fifty_move_draw());
+    let position = position_after("position fen 4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80 moves e2e3");
+    assert!(!position.is_fifty_move_draw());
+
+    // A check on the hundredth move is still a draw, a checkmate is not
+    let position = position_after("position fen 4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80 moves a1a8");
+    assert!(position.is_check(&position.get_turn()));
+    assert!(position.is_fifty_move_draw());
+    let position = position_after("position fen 4k3/R7/8/8/8/8/4P3/1R2K3 w - - 99 80 moves b1b8");
+    assert!(!position.is_fifty_move_draw());
+}
+
+#[test]
+fn draw_by_insufficient_material() {
+    for (fen, insufficient) in [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/2B1K3 b - - 0 1", true),
+        ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+        ("3bk3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("3bk3/8/8/8/8/B7/8/2B1K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/3RK3 w - - 0 1", false),
+    ] {
+        assert_eq!(
+            Position::from_fen(fen).is_insufficient_material(),
+            insufficient,
+            "{}",
+            fen
+        );
+    }
+}
+
+fn search_score(position: &mut Position, depth: usize) -> (i32, (i8, i8)) {
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..Default::default()
+    };
+    let result = search(
+        position,
+        &limits,
+        &TranspositionTable::new(1),
+        &SearchSignals::default(),
+        &SearchOptions::default(),
+    );
+    let best_move = result.best_move.unwrap();
+    (result.score, (best_move.source, best_move.destination))
+}
+
+#[test]
+fn search_saves_a_lost_position_by_repetition() {
+    // Kh1 repeats the position a third time, Kf1 loses to the queen
+    let mut position = position_after(
+        "position fen k7/8/8/8/8/8/q7/7K b - - 0 1 moves a2b2 h1g1 b2a2 g1h1 a2b2 h1g1 b2a2",
+    );
+    assert_eq!(search_score(&mut position, 5), (0, (6, 7)));
+
+    // The winning side avoids the repetition
+    let mut position = position_after(
+        "position fen k7/8/8/8/8/8/q7/7K b - - 0 1 moves a2b2 h1g1 b2a2 g1h1 a2b2 h1g1",
+    );
+    let (score, best_move) = search_score(&mut position, 5);
+    assert!(score > 0);
+    assert_ne!(best_move, (9, 8));
+}
+
+#[test]
+fn search_scores_dead_positions_as_draws() {
+    let mut position = Position::from_fen("8/8/4k3/8/8/2N1K3/8/8 w - - 0 1");
+    assert_eq!(search_score(&mut position, 4).0, 0);
+}
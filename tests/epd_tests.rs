@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::BufReader;
+use zeno::epd::{EpdError, EpdRecord, run_suite};
+use zeno::position::{FenError, Position};
+use zeno::san::SanError;
+use zeno::search::{SearchLimits, SearchOptions};
+use zeno::transposition_table::TranspositionTable;
+use zeno::uci::uci_move;
+
+#[test]
+fn epd_parses_operations() {
+    let record = EpdRecord::parse(
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6 Nf7+; am Qxg7?; id \"WAC.001\"; c0 \"mate; in 2\";",
+    )
+    .unwrap();
+    assert_eq!(
+        record.get_position(),
+        &Position::from_fen("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1")
+    );
+    assert_eq!(record.get_id(), Some("WAC.001"));
+    assert_eq!(record.get_comment(), Some("mate; in 2"));
+    assert_eq!(
+        record.get_operation("bm"),
+        Some(["Qg6".to_string(), "Nf7+".to_string()].as_slice())
+    );
+    assert_eq!(record.get_operations().len(), 4);
+    assert_eq!(record.get_operation("pv"), None);
+
+    let position = record.get_position();
+    let best_moves = record.get_best_moves().unwrap();
+    assert_eq!(
+        best_moves,
+        [
+            uci_move("g3g6", position).unwrap(),
+            uci_move("e5f7", position).unwrap()
+        ]
+    );
+    assert_eq!(
+        record.get_avoid_moves().unwrap(),
+        [uci_move("g3g7", position).unwrap()]
+    );
+    assert_eq!(record.is_solved_by(&best_moves[1]), Ok(true));
+    assert_eq!(
+        record.is_solved_by(&uci_move("g3g7", position).unwrap()),
+        Ok(false)
+    );
+    assert_eq!(
+        record.is_solved_by(&uci_move("a2a3", position).unwrap()),
+        Ok(false)
+    );
+
+    // Move counters, and a last operation without its semicolon
+    let record = EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 b - - hmvc 12; fmvn 40; id test").unwrap();
+    assert_eq!(
+        record.get_position().to_fen(),
+        "4k3/8/8/8/8/8/8/4K3 b - - 12 40"
+    );
+    assert_eq!(record.get_id(), Some("test"));
+    let record = EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert!(record.get_operations().is_empty());
+}
+
+#[test]
+fn epd_errors() {
+    assert_eq!(
+        EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w -"),
+        Err(EpdError::MissingField("en passant"))
+    );
+    assert_eq!(
+        EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - id \"unterminated;"),
+        Err(EpdError::UnterminatedString)
+    );
+    assert_eq!(
+        EpdRecord::parse("4k3/8/8/8/8/8/8/8 w - - id \"no king\";"),
+        Err(EpdError::InvalidFen(FenError::MissingKing(
+            zeno::utils::PieceColor::White
+        )))
+    );
+    let record = EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;").unwrap();
+    assert_eq!(
+        record.get_best_moves(),
+        Err(EpdError::InvalidMove(SanError::IllegalMove(
+            "Qh5".to_string()
+        )))
+    );
+}
+
+#[test]
+fn epd_suite_runner() {
+    let limits = SearchLimits {
+        depth: Some(4),
+        ..Default::default()
+    };
+    let file = File::open("tests/data/tactics.epd").unwrap();
+    let result = run_suite(
+        BufReader::new(file),
+        &limits,
+        &SearchOptions::default(),
+        &TranspositionTable::new(1),
+    );
+    assert_eq!(result, (4, 4));
+}
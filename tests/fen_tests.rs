@@ -0,0 +1,202 @@
+use zeno::perft::perft;
+use zeno::position::{FenError, Position};
+use zeno::uci::uci_position;
+use zeno::utils::PieceColor;
+
+const PERFT_SUITE: &str = include_str!("data/perft.epd");
+
+// Positions with en passant squares, castling rights of a single side and larger move counters
+const FENS: [&str; 6] = [
+    "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    "r3k3/8/8/8/3pP3/8/8/4K2R b Kq e3 0 41",
+    "8/8/4k3/8/8/4K3/8/8 b - - 99 120",
+    "1rk3r1/pppppppp/8/8/8/8/PPPPPPPP/1RK3R1 w GBgb - 0 1",
+    "rk2r3/8/8/8/8/8/8/RK2R3 b Ee - 12 30",
+];
+
+fn perft_suite() -> impl Iterator<Item = (&'static str, Vec<u64>)> {
+    PERFT_SUITE
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut fields = line.split(';');
+            let fen = fields.next().unwrap().trim();
+            let counts = fields
+                .map(|field| field.split_whitespace().nth(1).unwrap().parse().unwrap())
+                .collect();
+            (fen, counts)
+        })
+}
+
+#[test]
+fn fen_round_trip() {
+    for fen in perft_suite().map(|(fen, _)| fen).chain(FENS) {
+        let position = Position::from_fen(fen);
+        assert_eq!(position.to_fen(), fen);
+        assert_eq!(position.to_string(), fen);
+        assert_eq!(Position::from_fen(&position.to_fen()), position);
+    }
+}
+
+#[test]
+fn fen_perft_suite() {
+    for (fen, counts) in perft_suite() {
+        for (depth, expected) in counts.iter().enumerate().take(3) {
+            let result = perft(depth as i32 + 1, &mut Position::from_fen(fen));
+            assert_eq!(result, *expected, "{} depth {}", fen, depth + 1);
+        }
+    }
+}
+
+#[test]
+fn fen_move_counters() {
+    let mut position =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let mut fens = vec![];
+    for moves in ["e2e4", "e2e4 g8f6", "e2e4 g8f6 g1f3", "e2e4 g8f6 g1f3 f6e4"] {
+        uci_position(&format!("position startpos moves {}", moves), &mut position);
+        fens.push(position.to_fen());
+        assert_eq!(
+            position.get_full_move_number(),
+            1 + moves.split(' ').count() as u16 / 2
+        );
+    }
+    assert_eq!(
+        fens,
+        [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2",
+            "rnbqkb1r/pppppppp/5n2/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 2 2",
+            "rnbqkb1r/pppppppp/8/8/4n3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3",
+        ]
+    );
+    assert_eq!(position.get_half_move_clock(), 0);
+
+    // Castling counts as a reversible move
+    let mut position = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 7 20");
+    uci_position(
+        "position fen r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 7 20 moves e8c8 e1g1",
+        &mut position,
+    );
+    assert_eq!(position.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 b - - 9 21");
+}
+
+#[test]
+fn fen_errors() {
+    for (fen, error) in [
+        ("", FenError::MissingField("board")),
+        ("4k3/8/8/8/8/8/8/4K3", FenError::MissingField("turn")),
+        (
+            "4k3/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::WrongNumberOfRanks(7),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3/8 w - - 0 1",
+            FenError::WrongNumberOfRanks(9),
+        ),
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", FenError::InvalidPiece('X')),
+        ("4k3/8/8/0/8/8/8/4K3 w - - 0 1", FenError::InvalidPiece('0')),
+        (
+            "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+            FenError::WrongRankLength(1),
+        ),
+        (
+            "4k3/8/8/7/8/8/8/4K3 w - - 0 1",
+            FenError::WrongRankLength(5),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/8 w - - 0 1",
+            FenError::MissingKing(PieceColor::White),
+        ),
+        (
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::MissingKing(PieceColor::Black),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/K3K3 w - - 0 1",
+            FenError::TooManyKings(PieceColor::White),
+        ),
+        (
+            "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::PawnOnBackRank(56),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2p w - - 0 1",
+            FenError::PawnOnBackRank(7),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            FenError::InvalidTurn("x".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/R3K2R w KX - 0 1",
+            FenError::InvalidCastling("KX".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/R3K3 w K - 0 1",
+            FenError::InconsistentCastlingRights('K'),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2R w Q - 0 1",
+            FenError::InconsistentCastlingRights('Q'),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/R3K3 w q - 0 1",
+            FenError::InconsistentCastlingRights('q'),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/R3K3 w B - 0 1",
+            FenError::InconsistentCastlingRights('B'),
+        ),
+        (
+            "4k3/8/8/8/8/8/4K3/R6R w K - 0 1",
+            FenError::InconsistentCastlingRights('K'),
+        ),
+        (
+            "4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1",
+            FenError::ImpossibleEnPassant("e3".to_string()),
+        ),
+        (
+            "4k3/8/8/8/4P3/8/8/4K3 b - e4 0 1",
+            FenError::ImpossibleEnPassant("e4".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+            FenError::ImpossibleEnPassant("e3".to_string()),
+        ),
+        (
+            "4k3/8/8/8/4P3/4N3/8/4K3 b - e3 0 1",
+            FenError::ImpossibleEnPassant("e3".to_string()),
+        ),
+        (
+            "4k3/8/8/8/4P3/8/8/4K3 b - e9 0 1",
+            FenError::ImpossibleEnPassant("e9".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            FenError::InvalidMoveCounter("x".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 -1",
+            FenError::InvalidMoveCounter("-1".to_string()),
+        ),
+        ("4k2R/8/8/8/8/8/8/4K3 w - - 0 1", FenError::OpponentInCheck),
+    ] {
+        assert_eq!(Position::try_from_fen(fen), Err(error.clone()), "{}", fen);
+        assert_eq!(fen.parse::<Position>(), Err(error), "{}", fen);
+    }
+
+    let fen = "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1";
+    assert_eq!(fen.parse::<Position>(), Ok(Position::from_fen(fen)));
+    assert_eq!(
+        FenError::PawnOnBackRank(7).to_string(),
+        "Pawn on a back rank: h1"
+    );
+}
+
+#[test]
+#[should_panic(expected = "More than one Black king")]
+fn fen_from_fen_panics() {
+    Position::from_fen("k6k/8/8/8/8/8/8/4K3 w - - 0 1");
+}
@@ -0,0 +1,88 @@
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_pseudo_legal_moves;
+use zeno::position::Position;
+use zeno::utils::PieceColor;
+use zeno::zobrist_hash::hash_position;
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn pseudo_legal_moves(position: &Position) -> MoveList {
+    let mut moves = MoveList::new();
+    generate_pseudo_legal_moves(position, &position.get_turn(), &mut moves);
+    moves
+}
+
+// Plays and takes back every pseudo-legal move down to the given depth,
+// checking that the position is restored exactly each time
+fn assert_round_trip(depth: i32, position: &mut Position) -> u64 {
+    if depth == 0 {
+        return 0;
+    }
+    let turn = position.get_turn();
+    let mut moves = MoveList::new();
+    generate_pseudo_legal_moves(position, &turn, &mut moves);
+    let mut number_of_round_trip = 0;
+    for mov in moves {
+        let before = position.clone();
+        let undo_move = position.make_move(&mov, true);
+        assert_eq!(undo_move.turn, turn);
+        if !position.is_check(&turn) {
+            number_of_round_trip += assert_round_trip(depth - 1, position);
+        }
+        position.unmake_move(&undo_move);
+        assert_eq!(*position, before, "round trip failed for {:?}", mov);
+        number_of_round_trip += 1;
+    }
+    number_of_round_trip
+}
+
+#[test]
+fn make_unmake_startpos() {
+    let mut position = Position::from_fen(STARTPOS);
+    assert!(assert_round_trip(3, &mut position) > 8_902);
+}
+
+#[test]
+fn make_unmake_kiwipete() {
+    let mut position = Position::from_fen(KIWIPETE);
+    assert!(assert_round_trip(3, &mut position) > 97_862);
+}
+
+#[test]
+fn make_unmake_restores_castling_and_en_passant() {
+    let mut position = Position::from_fen(KIWIPETE);
+    let before = position.clone();
+
+    // O-O then a double pawn push, taken back in reverse order
+    let castle = pseudo_legal_moves(&position)
+        .into_iter()
+        .find(|m| m.move_type == zeno::utils::MoveType::ShortCastle)
+        .unwrap();
+    let undo_castle = position.make_move(&castle, true);
+    let push = pseudo_legal_moves(&position)
+        .into_iter()
+        .find(|m| m.source == 50 && m.destination == 34)
+        .unwrap();
+    let undo_push = position.make_move(&push, true);
+    assert_eq!(position.get_en_passant(), Some(42));
+
+    position.unmake_move(&undo_push);
+    position.unmake_move(&undo_castle);
+    assert_eq!(position, before);
+}
+
+#[test]
+fn make_unmake_null_move() {
+    let mut position =
+        Position::from_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2");
+    let before = position.clone();
+
+    let undo_move = position.make_null_move();
+    assert_eq!(position.get_turn(), PieceColor::Black);
+    assert_eq!(position.get_en_passant(), None);
+    assert_eq!(position.key(), hash_position(&position));
+
+    position.unmake_null_move(&undo_move);
+    assert_eq!(position, before);
+}
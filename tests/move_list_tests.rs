@@ -0,0 +1,57 @@
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_legal_moves;
+use zeno::position::Position;
+use zeno::utils::{Move, MoveType};
+
+fn new_move(source: i8, destination: i8, move_score: i32) -> Move {
+    Move {
+        source,
+        destination,
+        move_type: MoveType::Normal,
+        move_score,
+    }
+}
+
+#[test]
+fn move_list_push_and_iterate() {
+    let mut moves = MoveList::new();
+    assert!(moves.is_empty());
+    moves.push(new_move(12, 28, 0));
+    moves.push(new_move(6, 21, 0));
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[1].destination, 21);
+    assert_eq!(moves.iter().map(|m| m.source).collect::<Vec<i8>>(), [12, 6]);
+    assert_eq!(moves.into_iter().count(), 2);
+
+    // The score is not part of the move
+    assert!(moves.contains(&new_move(6, 21, 500)));
+    assert!(!moves.contains(&new_move(21, 6, 0)));
+
+    moves.clear();
+    assert!(moves.is_empty());
+    assert!(!moves.contains(&new_move(12, 28, 0)));
+}
+
+#[test]
+fn move_list_sort_by_score() {
+    let mut moves = MoveList::new();
+    for (index, score) in [10, -5, 300, 10, 0].iter().enumerate() {
+        moves.push(new_move(index as i8, 63, *score));
+    }
+    moves.sort_by_score();
+    assert_eq!(
+        moves.iter().map(|m| m.source).collect::<Vec<i8>>(),
+        [2, 0, 3, 4, 1]
+    );
+}
+
+#[test]
+fn move_list_is_filled_by_the_generator() {
+    // The moves are appended to the ones already in the list
+    let position = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let mut moves = MoveList::new();
+    generate_legal_moves(&position, &position.get_turn(), &mut moves);
+    assert_eq!(moves.len(), 20);
+    generate_legal_moves(&position, &position.get_turn(), &mut moves);
+    assert_eq!(moves.len(), 40);
+}
@@ -0,0 +1,180 @@
+use zeno::move_list::MoveList;
+use zeno::move_picker::{HistoryTables, MovePicker, is_quiet};
+use zeno::moves_generator::generate_pseudo_legal_moves;
+use zeno::position::Position;
+use zeno::utils::{Move, MoveType, PieceColor, PieceType};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const PROMOTIONS: &str = "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1";
+
+fn new_move(source: i8, destination: i8, move_type: MoveType) -> Move {
+    Move {
+        source,
+        destination,
+        move_type,
+        move_score: 0,
+    }
+}
+
+fn picked_moves(
+    position: &Position,
+    mut move_picker: MovePicker,
+    tables: &HistoryTables,
+) -> Vec<u16> {
+    let mut moves = vec![];
+    while let Some(mov) = move_picker.next(position, tables) {
+        moves.push(mov.to_u16());
+    }
+    moves
+}
+
+fn pseudo_legal_moves(position: &Position) -> MoveList {
+    let mut moves = MoveList::new();
+    generate_pseudo_legal_moves(position, &position.get_turn(), &mut moves);
+    moves
+}
+
+fn generated_moves(position: &Position) -> Vec<u16> {
+    pseudo_legal_moves(position)
+        .iter()
+        .map(|m| m.to_u16())
+        .collect()
+}
+
+// The capture loses material when a defended victim is worth less than the attacker
+fn is_bad_capture(position: &Position, mov: &Move) -> bool {
+    let attacker = position.get_piece_on_square(&mov.source);
+    let victim = position.get_captured_piece(mov);
+    victim != PieceType::None
+        && mov.move_type.promotion_piece().is_none()
+        && (victim as i32) < attacker.piece_type as i32
+        && position.is_square_attack_by(&mov.destination, &attacker.color.opposite())
+}
+
+#[test]
+fn move_picker_yields_every_move_once() {
+    let tables = HistoryTables::new();
+    for fen in [KIWIPETE, PROMOTIONS] {
+        let position = Position::from_fen(fen);
+        let mut expected = generated_moves(&position);
+        expected.sort();
+
+        let mut picked = picked_moves(&position, MovePicker::new(None, [None; 2], None), &tables);
+        picked.sort();
+        assert_eq!(picked, expected, "{}", fen);
+
+        // Special moves already generated, or not playable in this position, are not duplicated
+        let hash_move = pseudo_legal_moves(&position)[3];
+        let killers = [Some(hash_move), Some(new_move(0, 63, MoveType::Normal))];
+        let counter_move = Some(pseudo_legal_moves(&position)[5]);
+        let mut picked = picked_moves(
+            &position,
+            MovePicker::new(Some(hash_move), killers, counter_move),
+            &tables,
+        );
+        assert_eq!(picked[0], hash_move.to_u16());
+        picked.sort();
+        assert_eq!(picked, expected, "{}", fen);
+    }
+}
+
+#[test]
+fn move_picker_drops_a_hash_move_that_is_not_playable() {
+    let position = Position::from_fen(KIWIPETE);
+    let tables = HistoryTables::new();
+    let hash_move = new_move(12, 28, MoveType::Normal);
+    let picked = picked_moves(
+        &position,
+        MovePicker::new(Some(hash_move), [None; 2], None),
+        &tables,
+    );
+    assert!(!picked.contains(&hash_move.to_u16()));
+    assert_eq!(picked.len(), generated_moves(&position).len());
+}
+
+#[test]
+fn move_picker_stages_are_ordered() {
+    let position = Position::from_fen(KIWIPETE);
+    let mut tables = HistoryTables::new();
+
+    // e1g1 as hash move, a2a3 and b2b3 as killers, g2g3 as countermove of the previous move
+    let hash_move = new_move(4, 6, MoveType::ShortCastle);
+    let killers = [
+        Some(new_move(8, 16, MoveType::Normal)),
+        Some(new_move(9, 17, MoveType::Normal)),
+    ];
+    let counter_move = new_move(14, 22, MoveType::Normal);
+    // e1f1, then d2e3, are the best quiet moves according to the history
+    let history_move = new_move(4, 5, MoveType::Normal);
+    tables.update_quiet_cutoff(&PieceColor::White, 5, 10, &history_move, &None, &[]);
+    let other_history_move = new_move(11, 20, MoveType::Normal);
+    tables.update_quiet_cutoff(&PieceColor::White, 5, 5, &other_history_move, &None, &[]);
+
+    let mut move_picker = MovePicker::new(Some(hash_move), killers, Some(counter_move));
+    let mut stages = vec![];
+    while let Some(mov) = move_picker.next(&position, &tables) {
+        let stage = if mov.to_u16() == hash_move.to_u16() {
+            0
+        } else if is_bad_capture(&position, &mov) {
+            8
+        } else if !is_quiet(&position, &mov) {
+            1
+        } else if killers.iter().flatten().any(|k| k.to_u16() == mov.to_u16()) {
+            3
+        } else if mov.to_u16() == counter_move.to_u16() {
+            4
+        } else if mov.to_u16() == history_move.to_u16() {
+            5
+        } else if mov.to_u16() == other_history_move.to_u16() {
+            6
+        } else {
+            7
+        };
+        stages.push(stage);
+    }
+
+    assert!(stages.is_sorted(), "{:?}", stages);
+    for stage in [0, 1, 3, 4, 5, 6, 7, 8] {
+        assert!(
+            stages.contains(&stage),
+            "stage {} missing in {:?}",
+            stage,
+            stages
+        );
+    }
+    assert_eq!(stages.iter().filter(|s| **s == 3).count(), 2);
+}
+
+#[test]
+fn history_tables_learn_from_cutoffs() {
+    let mut tables = HistoryTables::new();
+    let previous_move = Some(new_move(52, 36, MoveType::Normal));
+    let cutoff = new_move(6, 21, MoveType::Normal);
+    let tried = new_move(1, 18, MoveType::Normal);
+    let white = PieceColor::White;
+
+    tables.update_quiet_cutoff(&white, 3, 4, &cutoff, &previous_move, &[tried]);
+    assert_eq!(
+        tables.get_killers(3)[0].map(|m| m.to_u16()),
+        Some(cutoff.to_u16())
+    );
+    assert_eq!(
+        tables.get_counter_move(&previous_move).map(|m| m.to_u16()),
+        Some(cutoff.to_u16())
+    );
+    assert!(tables.get_history(&white, &cutoff) > 0);
+    assert!(tables.get_history(&white, &tried) < 0);
+    assert_eq!(tables.get_history(&PieceColor::Black, &cutoff), 0);
+
+    // The previous killer is kept as the second one
+    tables.update_quiet_cutoff(&white, 3, 4, &tried, &None, &[]);
+    let killers = tables.get_killers(3);
+    assert_eq!(killers[0].map(|m| m.to_u16()), Some(tried.to_u16()));
+    assert_eq!(killers[1].map(|m| m.to_u16()), Some(cutoff.to_u16()));
+
+    // The history saturates
+    for _ in 0..1000 {
+        tables.update_quiet_cutoff(&white, 3, 20, &cutoff, &None, &[]);
+    }
+    assert!(tables.get_history(&white, &cutoff) <= 16_384);
+}
@@ -0,0 +1,254 @@
+use zeno::move_list::MoveList;
+use zeno::moves_generator::{
+    GenerationMode, generate_legal_moves, generate_moves, generate_pseudo_legal_moves,
+    is_pseudo_legal,
+};
+use zeno::position::Position;
+use zeno::utils::{Move, MoveType};
+
+const PERFT_SUITE: &str = include_str!("data/perft.epd");
+
+fn legal_moves(position: &Position) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &position.get_turn(), &mut moves);
+    moves.into_iter().collect()
+}
+
+fn sorted_moves(moves: &[Move]) -> Vec<u16> {
+    let mut moves: Vec<u16> = moves.iter().map(|mov| mov.to_u16()).collect();
+    moves.sort();
+    moves
+}
+
+// The legal moves are the pseudo-legal moves which do not leave the king in check,
+// compared in every position down to the given depth
+fn assert_same_moves(depth: i32, position: &mut Position) {
+    let turn = position.get_turn();
+    let legal = legal_moves(position);
+    let mut pseudo_legal = MoveList::new();
+    generate_pseudo_legal_moves(position, &turn, &mut pseudo_legal);
+    let filtered: Vec<Move> = pseudo_legal
+        .into_iter()
+        .filter(|mov| {
+            let undo_move = position.make_move(mov, true);
+            let is_legal = !position.is_check(&turn);
+            position.unmake_move(&undo_move);
+            is_legal
+        })
+        .collect();
+    assert_eq!(
+        sorted_moves(&legal),
+        sorted_moves(&filtered),
+        "{}",
+        position
+    );
+
+    if depth > 1 {
+        for mov in legal {
+            let undo_move = position.make_move(&mov, true);
+            assert_same_moves(depth - 1, position);
+            position.unmake_move(&undo_move);
+        }
+    }
+}
+
+#[test]
+fn legal_moves_match_filtered_pseudo_legal_moves() {
+    for line in PERFT_SUITE.lines().filter(|line| !line.is_empty()) {
+        let fen = line.split(';').next().unwrap().trim();
+        assert_same_moves(3, &mut Position::from_fen(fen));
+    }
+}
+
+fn moves_of(position: &Position, mode: &GenerationMode) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_moves(position, &position.get_turn(), mode, &mut moves);
+    moves.into_iter().collect()
+}
+
+fn is_legal(position: &mut Position, mov: &Move) -> bool {
+    let turn = position.get_turn();
+    let undo_move = position.make_move(mov, true);
+    let is_legal = !position.is_check(&turn);
+    position.unmake_move(&undo_move);
+    is_legal
+}
+
+// Returns the number of positions in check met down to the given depth
+fn assert_modes_add_up(depth: i32, position: &mut Position) -> usize {
+    let turn = position.get_turn();
+    let all = moves_of(position, &GenerationMode::All);
+    let captures = moves_of(position, &GenerationMode::Captures);
+    let quiets = moves_of(position, &GenerationMode::Quiets);
+    let union: Vec<Move> = captures.iter().chain(quiets.iter()).copied().collect();
+    assert_eq!(sorted_moves(&union), sorted_moves(&all), "{}", position);
+    assert!(all.iter().all(|mov| is_pseudo_legal(position, mov)));
+
+    // The quiet moves which give check, apart from castling and promotions
+    let expected_checks: Vec<Move> = quiets
+        .iter()
+        .filter(|mov| {
+            mov.move_type == MoveType::Normal && {
+                let undo_move = position.make_move(mov, true);
+                let gives_check = position.is_check(&turn.opposite());
+                position.unmake_move(&undo_move);
+                gives_check
+            }
+        })
+        .copied()
+        .collect();
+    let quiet_checks = moves_of(position, &GenerationMode::QuietChecks);
+    assert_eq!(
+        sorted_moves(&quiet_checks),
+        sorted_moves(&expected_checks),
+        "{}",
+        position
+    );
+
+    let mut checks = 0;
+    if position.is_check(&turn) {
+        checks += 1;
+        let legal_evasions: Vec<Move> = moves_of(position, &GenerationMode::Evasions)
+            .into_iter()
+            .filter(|mov| is_legal(position, mov))
+            .collect();
+        assert_eq!(
+            sorted_moves(&legal_evasions),
+            sorted_moves(&legal_moves(position)),
+            "{}",
+            position
+        );
+    }
+
+    if depth > 0 {
+        for mov in legal_moves(position) {
+            let undo_move = position.make_move(&mov, true);
+            checks += assert_modes_add_up(depth - 1, position);
+            position.unmake_move(&undo_move);
+        }
+    }
+    checks
+}
+
+#[test]
+fn generation_modes_add_up_to_all_moves() {
+    let mut checks = 0;
+    for line in PERFT_SUITE.lines().filter(|line| !line.is_empty()) {
+        let fen = line.split(';').next().unwrap().trim();
+        checks += assert_modes_add_up(2, &mut Position::from_fen(fen));
+    }
+    assert!(checks > 0);
+}
+
+#[test]
+fn pseudo_legal_moves_from_elsewhere() {
+    let position = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let new_move = |source: i8, destination: i8, move_type: MoveType| Move {
+        source,
+        destination,
+        move_type,
+        move_score: 0,
+    };
+    assert!(is_pseudo_legal(
+        &position,
+        &new_move(4, 6, MoveType::ShortCastle)
+    ));
+    assert!(is_pseudo_legal(
+        &position,
+        &new_move(49, 56, MoveType::PawnToRook)
+    ));
+    // A pawn reaching the last rank has to promote, a rook cannot
+    assert!(!is_pseudo_legal(
+        &position,
+        &new_move(49, 57, MoveType::Normal)
+    ));
+    assert!(!is_pseudo_legal(
+        &position,
+        &new_move(0, 8, MoveType::PawnToQueen)
+    ));
+    // Pieces of the opponent, empty squares and a king moving two squares without castling
+    assert!(!is_pseudo_legal(
+        &position,
+        &new_move(56, 48, MoveType::Normal)
+    ));
+    assert!(!is_pseudo_legal(
+        &position,
+        &new_move(20, 28, MoveType::Normal)
+    ));
+    assert!(!is_pseudo_legal(
+        &position,
+        &new_move(4, 2, MoveType::Normal)
+    ));
+}
+
+#[test]
+fn legal_moves_in_double_check() {
+    // Checked by the rook and the knight, the queen cannot take the knight
+    let position = Position::from_fen("4r1k1/8/8/8/3Q4/3n4/8/4K3 w - - 0 1");
+    let moves = legal_moves(&position);
+    assert!(moves.iter().all(|mov| mov.source == 4));
+    assert_eq!(moves.len(), 3);
+
+    // In a single check, the queen can take the knight
+    let position = Position::from_fen("6k1/8/8/8/3Q4/3n4/8/4K3 w - - 0 1");
+    assert!(legal_moves(&position).iter().any(|mov| mov.source == 27));
+}
+
+#[test]
+fn legal_moves_of_pinned_pieces() {
+    // The bishop cannot leave the file, the rook moves along it up to the pinning rook
+    let position = Position::from_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1");
+    assert!(legal_moves(&position).iter().all(|mov| mov.source == 4));
+
+    let position = Position::from_fen("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1");
+    let rook_moves: Vec<i8> = legal_moves(&position)
+        .iter()
+        .filter(|mov| mov.source == 12)
+        .map(|mov| mov.destination)
+        .collect();
+    assert_eq!(rook_moves, [20, 28, 36, 44, 52]);
+}
+
+#[test]
+fn legal_en_passant_captures() {
+    // Both pawns leave the rank, the queen would take the king
+    let position = Position::from_fen("8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1");
+    assert!(
+        legal_moves(&position)
+            .iter()
+            .all(|mov| mov.move_type != MoveType::EnPassant)
+    );
+
+    // Taking the checking pawn en passant gets out of check
+    let position = Position::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+    assert!(
+        legal_moves(&position)
+            .iter()
+            .any(|mov| mov.move_type == MoveType::EnPassant)
+    );
+}
+
+#[test]
+fn legal_chess960_castling_behind_the_rook() {
+    // Once the rook leaves b1, the slider on a1 attacks the king on c1
+    for fen in [
+        "3k4/8/8/8/8/8/8/rR1K4 w B - 0 1",
+        "3k4/8/8/8/8/8/8/qR1K4 w B - 0 1",
+    ] {
+        let moves = legal_moves(&Position::from_fen(fen));
+        let mut destinations: Vec<(i8, i8)> = moves
+            .iter()
+            .map(|mov| (mov.source, mov.destination))
+            .collect();
+        destinations.sort();
+        assert_eq!(
+            destinations,
+            [(1, 0), (1, 2), (3, 2), (3, 4), (3, 10), (3, 11), (3, 12)]
+        );
+        assert!(
+            moves
+                .iter()
+                .all(|mov| mov.move_type != MoveType::LongCastle)
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use zeno::options::{OptionError, OptionType, OptionValue, UciOption, UciOptions};
+
+fn options() -> UciOptions {
+    let mut options = UciOptions::new();
+    options.add(
+        "Hash",
+        OptionType::Spin {
+            default: 16,
+            min: 1,
+            max: 1024,
+        },
+    );
+    options.add("Ponder", OptionType::Check { default: false });
+    options.add(
+        "Style",
+        OptionType::Combo {
+            default: "Normal",
+            vars: &["Solid", "Normal", "Risky"],
+        },
+    );
+    options.add("Clear Hash", OptionType::Button);
+    options.add("SyzygyPath", OptionType::String { default: "" });
+    options
+}
+
+#[test]
+fn option_lines() {
+    let lines: Vec<String> = options().iter().map(|o| o.to_string()).collect();
+    assert_eq!(
+        lines,
+        [
+            "option name Hash type spin default 16 min 1 max 1024",
+            "option name Ponder type check default false",
+            "option name Style type combo default Normal var Solid var Normal var Risky",
+            "option name Clear Hash type button",
+            "option name SyzygyPath type string default <empty>",
+        ]
+    );
+    assert_eq!(
+        UciOption::new("Ponder", OptionType::Check { default: true }).get_value(),
+        &OptionValue::Check(true)
+    );
+}
+
+#[test]
+fn options_are_set_by_name() {
+    let mut options = options();
+    assert_eq!(options.set("hash", "64"), Ok("Hash"));
+    assert_eq!(options.get_spin("Hash"), 64);
+    // Out of bounds values are clamped
+    options.set("Hash", "100000").unwrap();
+    assert_eq!(options.get_spin("Hash"), 1024);
+    options.set("Hash", "0").unwrap();
+    assert_eq!(options.get_spin("Hash"), 1);
+
+    options.set("Ponder", "true").unwrap();
+    assert!(options.get_check("Ponder"));
+    options.set("style", "risky").unwrap();
+    assert_eq!(options.get_string("Style"), "Risky");
+    assert_eq!(options.set("Clear Hash", ""), Ok("Clear Hash"));
+    options.set("SyzygyPath", "/tb/wdl:/tb/dtz").unwrap();
+    assert_eq!(options.get_string("SyzygyPath"), "/tb/wdl:/tb/dtz");
+}
+
+#[test]
+fn options_reject_invalid_values() {
+    let mut options = options();
+    assert_eq!(
+        options.set("Threads", "4"),
+        Err(OptionError::UnknownOption("Threads".to_string()))
+    );
+    for (name, value) in [("Hash", "big"), ("Ponder", "yes"), ("Style", "Crazy")] {
+        assert_eq!(
+            options.set(name, value),
+            Err(OptionError::InvalidValue {
+                name: name.to_string(),
+                value: value.to_string()
+            })
+        );
+    }
+    // The previous values are kept
+    assert_eq!(options.get_spin("Hash"), 16);
+    assert!(!options.get_check("Ponder"));
+    assert_eq!(options.get_string("Style"), "Normal");
+    assert_eq!(
+        options.set("Hash", "big").unwrap_err().to_string(),
+        "Invalid value for option Hash: big"
+    );
+}
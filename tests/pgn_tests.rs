@@ -0,0 +1,235 @@
+use std::io::Cursor;
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_legal_moves;
+use zeno::pgn::{PgnError, PgnErrorKind, PgnGame, PgnReader, eval_comment};
+use zeno::position::Position;
+use zeno::san::SanError;
+use zeno::search::MATE;
+use zeno::uci::{STARTPOS, uci_move};
+use zeno::utils::PieceColor;
+
+const GAMES: &str = r#"[Event "Casual \"blitz\" game"]
+[Site "?"]
+[Date "2024.05.01"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 {The most common move} Nc6 (2... d6 3. d4 (3. Bc4 Be7) exd4)
+3. Bb5 $1 a6 ; Morphy defence
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5
+17. dxe5 Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4
+22. Bxc4 Nb6 23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7
+27. Qe3 Qg5 28. Qxg5 hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5
+33. f3 Bc8 34. Kf2 Bf5 35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3
+39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6 Nf2 42. g4 Bd3 43. Re6 1-0
+
+[Event "Endgame"]
+[FEN "8/8/8/4k3/8/8/4P3/4K3 w - - 0 60"]
+[SetUp "1"]
+[Result "*"]
+
+60. e4 {Opposition} Kxe4 61.Kd2 *
+
+[Event "Broken"]
+[Result "*"]
+
+1. e4 e5 2. Nf3 Nf6 3. Bb5
+4. Ke3 Nc6 *
+
+[Event "No result"]
+
+1. d4 d5 2. c4
+"#;
+
+fn read_games(pgn: &str) -> Vec<Result<PgnGame, PgnError>> {
+    PgnReader::new(Cursor::new(pgn)).collect()
+}
+
+#[test]
+fn pgn_reads_games() {
+    let games = read_games(GAMES);
+    assert_eq!(games.len(), 4);
+
+    let game = games[0].as_ref().unwrap();
+    assert_eq!(game.get_tag("Event"), Some("Casual \"blitz\" game"));
+    assert_eq!(game.get_tag("White"), Some("Alice"));
+    assert_eq!(game.get_result(), "1-0");
+    assert_eq!(game.get_moves().len(), 85);
+    assert_eq!(game.get_moves()[2].san, "Nf3");
+    assert_eq!(
+        game.get_moves()[2].comment.as_deref(),
+        Some("The most common move")
+    );
+    assert_eq!(game.get_moves()[4].nags, [1]);
+    assert_eq!(
+        game.get_moves()[5].comment.as_deref(),
+        Some("Morphy defence")
+    );
+    assert_eq!(game.get_moves()[8].san, "O-O");
+    // The variations are not part of the main line, they are kept on the move they replace
+    assert_eq!(game.get_moves()[3].san, "Nc6");
+    let variation = &game.get_moves()[3].variations[0];
+    let sans: Vec<&str> = variation.moves.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(sans, ["d6", "d4", "exd4"]);
+    let nested = &variation.moves[1].variations[0];
+    let sans: Vec<&str> = nested.moves.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(sans, ["Bc4", "Be7"]);
+    assert_eq!(
+        game.get_position().to_fen(),
+        "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43"
+    );
+
+    let game = games[1].as_ref().unwrap();
+    assert_eq!(game.get_start_position().get_full_move_number(), 60);
+    assert_eq!(game.get_result(), "*");
+    assert_eq!(
+        game.get_position().to_fen(),
+        "8/8/8/8/4k3/8/3K4/8 b - - 1 61"
+    );
+
+    // The illegal move is reported with its line, and the next games are still read
+    assert_eq!(
+        games[2],
+        Err(PgnError {
+            line: 30,
+            kind: PgnErrorKind::IllegalMove(SanError::IllegalMove("Ke3".to_string()))
+        })
+    );
+    assert_eq!(
+        games[2].as_ref().unwrap_err().to_string(),
+        "Line 30: Illegal move: Ke3"
+    );
+
+    let game = games[3].as_ref().unwrap();
+    assert_eq!(game.get_moves().len(), 3);
+    assert_eq!(game.get_result(), "*");
+}
+
+#[test]
+fn pgn_reports_malformed_games() {
+    for (pgn, error) in [
+        (
+            "[Event \"?\"\n\n1. e4 *\n",
+            PgnErrorKind::InvalidTag("[Event \"?\"".to_string()),
+        ),
+        ("1. e4 { never closed\n", PgnErrorKind::UnterminatedComment),
+        ("1. e4 (1. d4 d5\n", PgnErrorKind::UnbalancedVariation),
+        ("1. e4 e5) *\n", PgnErrorKind::UnbalancedVariation),
+        ("1. e4 (1. d4 d5 *\n", PgnErrorKind::UnbalancedVariation),
+        ("(1. d4) 1. e4 *\n", PgnErrorKind::VariationWithoutMove),
+    ] {
+        let games = read_games(pgn);
+        assert_eq!(games.len(), 1, "{}", pgn);
+        assert_eq!(games[0].as_ref().unwrap_err().kind, error, "{}", pgn);
+    }
+
+    let games = read_games("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n\n*\n");
+    assert!(matches!(
+        games[0].as_ref().unwrap_err().kind,
+        PgnErrorKind::InvalidFen(_)
+    ));
+}
+
+#[test]
+fn pgn_replays_variations() {
+    // The variation replaces 2... Nc6, it is played from the position with black to move
+    let pgn = "[Event \"?\"]\n\n1. e4 e5 2. Nf3 Nc6\n(3. Bb5) 3. Bb5 *\n";
+    let games = read_games(pgn);
+    assert_eq!(
+        games[0],
+        Err(PgnError {
+            line: 4,
+            kind: PgnErrorKind::IllegalMove(SanError::IllegalMove("Bb5".to_string()))
+        })
+    );
+
+    // A game with variations is read back as it was written
+    let game = read_games(GAMES)[0].clone().unwrap();
+    let pgn = game.to_string();
+    let movetext = pgn.replace('\n', " ");
+    assert!(movetext.contains("2. Nf3 { The most common move } 2... Nc6 ( 2... d6 3. d4"));
+    assert!(movetext.contains("3. d4 ( 3. Bc4 Be7 ) 3... exd4 ) 3. Bb5 $1 a6"));
+    let read = read_games(&pgn);
+    assert_eq!(read[0].as_ref().unwrap(), &game);
+}
+
+#[test]
+fn pgn_skips_the_rest_of_a_broken_game() {
+    // The tags after the invalid one still belong to the broken game
+    let pgn = "[Event \"Broken\"\n[Site \"?\"]\n[Result \"*\"]\n\n1. e4 *\n\n\
+               [Event \"Next\"]\n\n1. d4 *\n";
+    let games = read_games(pgn);
+    assert_eq!(games.len(), 2);
+    assert!(games[0].is_err());
+    assert_eq!(games[1].as_ref().unwrap().get_tag("Event"), Some("Next"));
+
+    // An illegal move is skipped up to the result of its game
+    let pgn = "1. e4 e5 2. Ke3\n3. Nf3 *\n[Event \"Next\"]\n1. d4 *\n";
+    let games = read_games(pgn);
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[1].as_ref().unwrap().get_tag("Event"), Some("Next"));
+}
+
+#[test]
+fn pgn_writes_games() {
+    let mut game = PgnGame::new(Position::from_fen(STARTPOS));
+    game.set_tag("White", "Zeno");
+    game.set_tag("TimeControl", "60+1");
+    for (uci, comment) in [
+        ("e2e4", Some(eval_comment(30, &PieceColor::White))),
+        ("e7e5", Some(eval_comment(-25, &PieceColor::Black))),
+        ("g1f3", None),
+        ("b8c6", None),
+    ] {
+        let mov = uci_move(uci, game.get_position()).unwrap();
+        game.push_move(&mov, comment);
+    }
+    game.set_result("1/2-1/2");
+
+    assert_eq!(
+        game.to_string(),
+        "[Event \"?\"]\n\
+         [Site \"?\"]\n\
+         [Date \"????.??.??\"]\n\
+         [Round \"?\"]\n\
+         [White \"Zeno\"]\n\
+         [Black \"?\"]\n\
+         [Result \"1/2-1/2\"]\n\
+         [TimeControl \"60+1\"]\n\
+         \n\
+         1. e4 { [%eval 0.30] } 1... e5 { [%eval 0.25] } 2. Nf3 Nc6 1/2-1/2\n"
+    );
+
+    // A game from a position with black to move, read back as it was written
+    let mut game = PgnGame::new(Position::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 12",
+    ));
+    game.set_tag("Event", "Quote \" and backslash \\");
+    for _ in 0..20 {
+        let position = game.get_position();
+        let mut moves = MoveList::new();
+        generate_legal_moves(position, &position.get_turn(), &mut moves);
+        let mov = moves[0];
+        game.push_move(&mov, None);
+    }
+    let pgn = game.to_string();
+    assert!(pgn.contains("[SetUp \"1\"]"));
+    assert!(pgn.contains("\n\n12... "));
+    assert!(pgn.lines().all(|line| line.len() <= 79));
+
+    let read = read_games(&pgn);
+    assert_eq!(read.len(), 1);
+    assert_eq!(read[0].as_ref().unwrap(), &game);
+}
+
+#[test]
+fn pgn_eval_comments() {
+    assert_eq!(eval_comment(135, &PieceColor::White), "[%eval 1.35]");
+    assert_eq!(eval_comment(135, &PieceColor::Black), "[%eval -1.35]");
+    assert_eq!(eval_comment(MATE - 3, &PieceColor::White), "[%eval #2]");
+    assert_eq!(eval_comment(MATE - 3, &PieceColor::Black), "[%eval #-2]");
+    assert_eq!(eval_comment(-(MATE - 2), &PieceColor::White), "[%eval #-1]");
+}
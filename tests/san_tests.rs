@@ -0,0 +1,144 @@
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_legal_moves;
+use zeno::position::Position;
+use zeno::san::SanError;
+use zeno::uci::{move_to_uci, uci_move};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn san(fen: &str, uci: &str) -> String {
+    let position = Position::from_fen(fen);
+    position.move_to_san(&uci_move(uci, &position).unwrap())
+}
+
+fn parsed(fen: &str, san: &str) -> Result<String, SanError> {
+    let position = Position::from_fen(fen);
+    position
+        .parse_san(san)
+        .map(|mov| move_to_uci(&mov, &position))
+}
+
+#[test]
+fn san_formatting() {
+    for (fen, uci, expected) in [
+        (KIWIPETE, "e1g1", "O-O"),
+        (KIWIPETE, "e1c1", "O-O-O"),
+        (KIWIPETE, "e5f7", "Nxf7"),
+        (KIWIPETE, "d5e6", "dxe6"),
+        (KIWIPETE, "a2a4", "a4"),
+        (KIWIPETE, "f3f6", "Qxf6"),
+        (KIWIPETE, "c3b1", "Nb1"),
+        // Disambiguation by file, by rank, then by square
+        ("4k3/8/8/8/8/8/6K1/R6R w - - 0 1", "a1d1", "Rad1"),
+        ("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a5a3", "R5a3"),
+        ("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "a4d4", "Qa4d4"),
+        ("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "h4e4", "Qhe4"),
+        // A pinned piece does not need to be told apart
+        ("4k3/8/8/b7/8/2N5/8/4K1N1 w - - 0 1", "g1e2", "Ne2"),
+        // Promotions, en passant, checks and checkmates
+        ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q", "b8=Q+"),
+        ("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n", "bxa8=N"),
+        (
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "e5f6",
+            "exf6",
+        ),
+        ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8", "Ra8#"),
+        ("5k2/8/8/8/8/8/8/R3K2R w K - 0 1", "e1g1", "O-O+"),
+    ] {
+        assert_eq!(san(fen, uci), expected, "{} {}", fen, uci);
+    }
+}
+
+#[test]
+fn san_parsing() {
+    for (fen, text, expected) in [
+        (KIWIPETE, "O-O", "e1g1"),
+        (KIWIPETE, "0-0-0", "e1c1"),
+        (KIWIPETE, "Nxf7", "e5f7"),
+        (KIWIPETE, "Nf7", "e5f7"),
+        (KIWIPETE, "nxf7+!?", "e5f7"),
+        (KIWIPETE, "dxe6", "d5e6"),
+        (KIWIPETE, "de6", "d5e6"),
+        (KIWIPETE, "d5xe6", "d5e6"),
+        (KIWIPETE, "a4", "a2a4"),
+        (KIWIPETE, "a2-a3", "a2a3"),
+        (KIWIPETE, "Ne5-d3", "e5d3"),
+        (KIWIPETE, "e5d3", "e5d3"),
+        (KIWIPETE, "e1g1", "e1g1"),
+        (KIWIPETE, "bd3", "e2d3"),
+        ("4k3/8/8/8/8/8/6K1/R6R w - - 0 1", "Rad1", "a1d1"),
+        ("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "Qa4d4", "a4d4"),
+        ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q+", "b7b8q"),
+        ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8N", "b7b8n"),
+        ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8r", "b7b8r"),
+        ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8", "b7b8q"),
+        ("4k3/8/8/8/8/8/8/1B2K3 w - - 0 1", "bc2", "b1c2"),
+    ] {
+        assert_eq!(
+            parsed(fen, text),
+            Ok(expected.to_string()),
+            "{} {}",
+            fen,
+            text
+        );
+    }
+}
+
+#[test]
+fn san_parsing_errors() {
+    for (fen, text, error) in [
+        (KIWIPETE, "Nf9", SanError::InvalidSyntax("Nf9".to_string())),
+        (KIWIPETE, "Zf3", SanError::InvalidSyntax("Zf3".to_string())),
+        (KIWIPETE, "", SanError::InvalidSyntax("".to_string())),
+        (
+            KIWIPETE,
+            "Nf3=Q",
+            SanError::InvalidSyntax("Nf3=Q".to_string()),
+        ),
+        (KIWIPETE, "Ke2", SanError::IllegalMove("Ke2".to_string())),
+        (KIWIPETE, "e5", SanError::IllegalMove("e5".to_string())),
+        (
+            "4k3/8/8/8/8/8/8/R3K2R b - - 0 1",
+            "O-O",
+            SanError::IllegalMove("O-O".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/6K1/R6R w - - 0 1",
+            "Rd1",
+            SanError::AmbiguousMove("Rd1".to_string()),
+        ),
+        (
+            "1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1",
+            "Qad4",
+            SanError::AmbiguousMove("Qad4".to_string()),
+        ),
+    ] {
+        assert_eq!(parsed(fen, text), Err(error), "{} {}", fen, text);
+    }
+}
+
+// Every legal move is written in a way that is read back as the same move
+#[test]
+fn san_round_trip() {
+    for fen in [
+        KIWIPETE,
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        "1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1",
+    ] {
+        let position = Position::from_fen(fen);
+        let mut moves = MoveList::new();
+        generate_legal_moves(&position, &position.get_turn(), &mut moves);
+        for mov in moves {
+            let san = position.move_to_san(&mov);
+            assert_eq!(
+                position.parse_san(&san).map(|m| m.to_u16()),
+                Ok(mov.to_u16()),
+                "{} {}",
+                fen,
+                san
+            );
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_legal_moves;
+use zeno::position::Position;
+use zeno::search::{
+    MATE, MATE_BOUND, MIN_ELO, SearchLimits, SearchOptions, SearchParameters, SearchSignals,
+    best_move, format_score, search,
+};
+use zeno::time_manager::TimeManager;
+use zeno::transposition_table::TranspositionTable;
+use zeno::utils::{Move, MoveType, PieceColor};
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn legal_moves(position: &Position) -> MoveList {
+    let mut moves = MoveList::new();
+    generate_legal_moves(position, &position.get_turn(), &mut moves);
+    moves
+}
+
+fn is_legal(position: &Position, mov: &Move) -> bool {
+    legal_moves(position).contains(mov)
+}
+
+#[test]
+fn time_manager_uses_the_whole_movetime() {
+    let limits = SearchLimits {
+        movetime: Some(1000),
+        ..Default::default()
+    };
+    let time_manager = TimeManager::new(&limits, &PieceColor::White, &SearchOptions::default());
+    assert_eq!(time_manager.get_soft_limit(), time_manager.get_hard_limit());
+    assert!(time_manager.get_hard_limit().unwrap() <= Duration::from_millis(1000));
+}
+
+#[test]
+fn time_manager_splits_the_clock_of_the_side_to_move() {
+    let limits = SearchLimits {
+        wtime: Some(60_000),
+        btime: Some(1_000),
+        winc: Some(1_000),
+        binc: Some(0),
+        ..Default::default()
+    };
+    let white = TimeManager::new(&limits, &PieceColor::White, &SearchOptions::default());
+    let black = TimeManager::new(&limits, &PieceColor::Black, &SearchOptions::default());
+
+    let white_soft = white.get_soft_limit().unwrap();
+    let white_hard = white.get_hard_limit().unwrap();
+    assert!(white_soft <= white_hard);
+    assert!(white_hard < Duration::from_millis(60_000));
+    assert!(black.get_hard_limit().unwrap() < Duration::from_millis(1_000));
+    assert!(black.get_soft_limit().unwrap() < white_soft);
+}
+
+#[test]
+fn time_manager_has_no_limit_when_infinite() {
+    let limits = SearchLimits {
+        wtime: Some(1_000),
+        infinite: true,
+        ..Default::default()
+    };
+    let time_manager = TimeManager::new(&limits, &PieceColor::White, &SearchOptions::default());
+    assert!(time_manager.get_hard_limit().is_none());
+    assert!(time_manager.can_start_iteration());
+}
+
+#[test]
+fn search_respects_the_depth_limit() {
+    let mut position = Position::from_fen(STARTPOS);
+    let limits = SearchLimits {
+        depth: Some(2),
+        ..Default::default()
+    };
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    assert!(is_legal(&position, &mov));
+    assert_eq!(position, Position::from_fen(STARTPOS));
+}
+
+#[test]
+fn search_respects_the_movetime() {
+    let mut position =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let limits = SearchLimits {
+        movetime: Some(300),
+        ..Default::default()
+    };
+    // Building the move generation tables is not part of the search time
+    legal_moves(&position);
+
+    let start = Instant::now();
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(1_500));
+    assert!(is_legal(&position, &mov));
+}
+
+#[test]
+fn search_respects_the_node_limit() {
+    let mut position = Position::from_fen(STARTPOS);
+    let limits = SearchLimits {
+        nodes: Some(5_000),
+        ..Default::default()
+    };
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    assert!(is_legal(&position, &mov));
+}
+
+#[test]
+fn search_finds_a_mate_in_one() {
+    let mut position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let limits = SearchLimits {
+        depth: Some(3),
+        ..Default::default()
+    };
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    assert_eq!((mov.source, mov.destination), (0, 56));
+}
+
+#[test]
+fn search_returns_a_move_when_getting_mated() {
+    // Kb8 is forced and Rh8 mates
+    let mut position = Position::from_fen("k7/8/1K6/8/8/8/8/7R b - - 0 1");
+    let limits = SearchLimits {
+        depth: Some(4),
+        ..Default::default()
+    };
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    assert_eq!((mov.source, mov.destination), (56, 57));
+}
+
+#[test]
+fn search_reports_a_mated_root() {
+    // Black is mated, there is no move to play and the mate is reported as mate 0
+    let mut position = Position::from_fen("k6R/8/1K6/8/8/8/8/8 b - - 0 1");
+    let limits = SearchLimits {
+        depth: Some(4),
+        ..Default::default()
+    };
+    let result = search(
+        &mut position,
+        &limits,
+        &TranspositionTable::new(1),
+        &SearchSignals::default(),
+        &SearchOptions::default(),
+    );
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, -MATE);
+    assert_eq!(format_score(result.score), "mate 0");
+    assert_eq!(format_score(-(MATE - 2)), "mate -1");
+    assert_eq!(format_score(MATE - 1), "mate 1");
+    assert_eq!(format_score(-35), "cp -35");
+}
+
+#[test]
+fn search_prefers_the_shortest_mate() {
+    // Qg7 mates at once, while a lot of other moves mate a bit later
+    let mut position = Position::from_fen("7k/8/5KQ1/8/8/8/8/8 w - - 0 1");
+    let limits = SearchLimits {
+        depth: Some(5),
+        ..Default::default()
+    };
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    let mut after = position.clone();
+    after.make_move(&mov, true);
+    let turn = after.get_turn();
+    assert!(after.is_check(&turn));
+    assert!(legal_moves(&after).is_empty());
+}
+
+#[test]
+fn search_finds_a_mate_with_each_technique_turned_off() {
+    // Kb6, Kb8 is forced and Rh8 mates
+    let defaults = SearchParameters::default();
+    let parameters = [
+        defaults.clone(),
+        SearchParameters {
+            pvs: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            null_move: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            lmr: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            reverse_futility: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            razoring: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            futility: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            lmp: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            see_pruning: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            check_extension: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            singular_extension: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            multi_cut: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            recapture_extension: true,
+            passed_pawn_extension: false,
+            ..defaults.clone()
+        },
+        SearchParameters {
+            pvs: false,
+            null_move: false,
+            lmr: false,
+            reverse_futility: false,
+            razoring: false,
+            futility: false,
+            lmp: false,
+            see_pruning: false,
+            check_extension: false,
+            singular_extension: false,
+            passed_pawn_extension: false,
+            ..defaults
+        },
+    ];
+    for parameters in parameters {
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            parameters: parameters.clone(),
+            ..Default::default()
+        };
+        let result = search(
+            &mut position,
+            &limits,
+            &TranspositionTable::new(1),
+            &SearchSignals::default(),
+            &options,
+        );
+        let mov = result.best_move.unwrap();
+        assert_eq!((mov.source, mov.destination), (42, 41), "{:?}", parameters);
+        assert_eq!(result.score, MATE - 3, "{:?}", parameters);
+    }
+}
+
+#[test]
+fn forward_pruning_saves_nodes() {
+    let nodes = |parameters: SearchParameters| {
+        let mut position = Position::from_fen(STARTPOS);
+        let limits = SearchLimits {
+            depth: Some(7),
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            parameters,
+            ..Default::default()
+        };
+        let result = search(
+            &mut position,
+            &limits,
+            &TranspositionTable::new(4),
+            &SearchSignals::default(),
+            &options,
+        );
+        result.nodes
+    };
+    let without_pruning = nodes(SearchParameters {
+        reverse_futility: false,
+        razoring: false,
+        futility: false,
+        lmp: false,
+        see_pruning: false,
+        ..Default::default()
+    });
+    assert!(nodes(SearchParameters::default()) < without_pruning);
+}
+
+#[test]
+fn search_extends_checks_beyond_the_horizon() {
+    // The Evergreen game: Qxd7+ Kxd7 Bf5+ Ke8 Bd7+ Kf8 Bxe7# takes seven plies, all checks
+    let score = |check_extension: bool| {
+        let mut position =
+            Position::from_fen("1r2k1r1/pbppnp1p/1b3P2/8/Q7/B1PB1q2/P4PPP/3R2K1 w - - 0 1");
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..Default::default()
+        };
+        let options = SearchOptions {
+            parameters: SearchParameters {
+                check_extension,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        search(
+            &mut position,
+            &limits,
+            &TranspositionTable::new(1),
+            &SearchSignals::default(),
+            &options,
+        )
+        .score
+    };
+    assert_eq!(score(true), MATE - 7);
+    assert!(score(false) < MATE_BOUND);
+}
+
+#[test]
+fn search_finds_the_winning_underpromotion() {
+    // Saavedra: c8=Q Rc4+ Qxc4 is stalemate, while c8=R threatens Ra8# and wins the rook
+    let mut position = Position::from_fen("8/2P5/8/8/3r4/8/2K5/k7 w - - 0 6");
+    let limits = SearchLimits {
+        depth: Some(8),
+        ..Default::default()
+    };
+    let result = search(
+        &mut position,
+        &limits,
+        &TranspositionTable::new(1),
+        &SearchSignals::default(),
+        &SearchOptions::default(),
+    );
+    let mov = result.best_move.unwrap();
+    assert_eq!((mov.source, mov.destination), (50, 58));
+    assert_eq!(mov.move_type, MoveType::PawnToRook);
+    assert!(result.score > 300);
+}
+
+#[test]
+fn search_sees_the_recapture_beyond_the_horizon() {
+    // Qxd5 wins a pawn at depth 1, but exd5 follows
+    let mut position = Position::from_fen("4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1");
+    let limits = SearchLimits {
+        depth: Some(1),
+        ..Default::default()
+    };
+    let mov = best_move(&mut position, &limits, &TranspositionTable::new(1)).unwrap();
+    assert_ne!((mov.source, mov.destination), (11, 35));
+}
+
+#[test]
+fn search_stops_on_signal() {
+    let signals = Arc::new(SearchSignals::new(false));
+    let thread_signals = Arc::clone(&signals);
+    let handle = thread::spawn(move || {
+        let mut position = Position::from_fen(STARTPOS);
+        let limits = SearchLimits {
+            infinite: true,
+            ..Default::default()
+        };
+        search(
+            &mut position,
+            &limits,
+            &TranspositionTable::new(1),
+            &thread_signals,
+            &SearchOptions::default(),
+        )
+    });
+
+    thread::sleep(Duration::from_millis(200));
+    assert!(!handle.is_finished());
+    let start = Instant::now();
+    signals.stop();
+    let result = handle.join().unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(is_legal(
+        &Position::from_fen(STARTPOS),
+        &result.best_move.unwrap()
+    ));
+}
+
+#[test]
+fn search_ignores_the_clock_while_pondering() {
+    let signals = Arc::new(SearchSignals::new(true));
+    let thread_signals = Arc::clone(&signals);
+    let handle = thread::spawn(move || {
+        let mut position = Position::from_fen(STARTPOS);
+        let limits = SearchLimits {
+            movetime: Some(50),
+            ponder: true,
+            ..Default::default()
+        };
+        search(
+            &mut position,
+            &limits,
+            &TranspositionTable::new(1),
+            &thread_signals,
+            &SearchOptions::default(),
+        )
+    });
+
+    thread::sleep(Duration::from_millis(300));
+    assert!(!handle.is_finished());
+    // The movetime is already over when the opponent plays the expected move
+    signals.ponderhit();
+    let result = handle.join().unwrap();
+
+    let mut position = Position::from_fen(STARTPOS);
+    let best_move = result.best_move.unwrap();
+    assert!(is_legal(&position, &best_move));
+    position.make_move(&best_move, true);
+    assert!(is_legal(&position, &result.ponder_move.unwrap()));
+}
+
+#[test]
+fn search_reports_a_legal_principal_variation() {
+    let mut position =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let limits = SearchLimits {
+        depth: Some(4),
+        ..Default::default()
+    };
+    let result = search(
+        &mut position,
+        &limits,
+        &TranspositionTable::new(1),
+        &SearchSignals::default(),
+        &SearchOptions::default(),
+    );
+    assert_eq!(result.depth, 4);
+    assert!(result.nodes > 0);
+    assert!(result.pv.len() >= 2);
+    assert_eq!(result.pv[0], result.best_move.unwrap());
+    assert_eq!(result.pv.get(1).copied(), result.ponder_move);
+    for mov in &result.pv {
+        assert!(is_legal(&position, mov));
+        position.make_move(mov, true);
+    }
+}
+
+#[test]
+fn search_keeps_the_principal_variation_on_a_warm_table() {
+    let limits = SearchLimits {
+        depth: Some(6),
+        ..Default::default()
+    };
+    let transposition_table = TranspositionTable::new(16);
+    for _ in 0..2 {
+        let mut position = Position::from_fen(STARTPOS);
+        let result = search(
+            &mut position,
+            &limits,
+            &transposition_table,
+            &SearchSignals::default(),
+            &SearchOptions::default(),
+        );
+        assert!(result.pv.len() > 1);
+        assert_eq!(result.pv.get(1).copied(), result.ponder_move);
+        for mov in &result.pv {
+            assert!(is_legal(&position, mov));
+            position.make_move(mov, true);
+        }
+    }
+}
+
+#[test]
+fn search_with_helper_threads_and_multiple_lines() {
+    let mut position =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let limits = SearchLimits {
+        depth: Some(5),
+        ..Default::default()
+    };
+    let options = SearchOptions {
+        threads: 4,
+        multi_pv: 3,
+        ..Default::default()
+    };
+    let result = search(
+        &mut position,
+        &limits,
+        &TranspositionTable::new(4),
+        &SearchSignals::default(),
+        &options,
+    );
+    assert_eq!(result.depth, 5);
+    assert_eq!(result.pv[0], result.best_move.unwrap());
+    for mov in &result.pv {
+        assert!(is_legal(&position, mov));
+        position.make_move(mov, true);
+    }
+}
+
+#[test]
+fn search_limits_its_strength() {
+    let mut position = Position::from_fen(STARTPOS);
+    let options = SearchOptions {
+        threads: 4,
+        elo: Some(MIN_ELO),
+        ..Default::default()
+    };
+    let result = search(
+        &mut position,
+        &SearchLimits::default(),
+        &TranspositionTable::new(1),
+        &SearchSignals::default(),
+        &options,
+    );
+    assert!(result.depth <= 3, "{}", result.depth);
+    assert!(is_legal(
+        &Position::from_fen(STARTPOS),
+        &result.best_move.unwrap()
+    ));
+}
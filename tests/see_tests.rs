@@ -0,0 +1,62 @@
+use zeno::position::Position;
+use zeno::uci::uci_move;
+
+fn assert_see(fen: &str, move_string: &str, expected: i32) {
+    let position = Position::from_fen(fen);
+    let mov = uci_move(move_string, &position).unwrap();
+    assert_eq!(position.see(&mov), expected, "{} {}", fen, move_string);
+    assert!(position.see_ge(&mov, expected), "{} {}", fen, move_string);
+    assert!(
+        !position.see_ge(&mov, expected + 1),
+        "{} {}",
+        fen,
+        move_string
+    );
+}
+
+#[test]
+fn see_of_simple_exchanges() {
+    // Undefended pawn
+    assert_see("4k3/8/8/3p4/8/2N5/8/4K3 w - - 0 1", "c3d5", 100);
+    // Knight defended by a pawn, taken by a pawn
+    assert_see("4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5", 220);
+    // Pawn defended by a pawn, taken by a rook
+    assert_see("4k3/8/4p3/3p4/8/8/8/3RK3 w - - 0 1", "d1d5", -400);
+    // Moving a piece to an attacked square
+    assert_see("4k3/8/4p3/8/8/8/8/3RK3 w - - 0 1", "d1d5", -500);
+    assert_see(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "e2e4",
+        0,
+    );
+}
+
+#[test]
+fn see_with_x_rays() {
+    // The rook behind the first one wins the pawn
+    assert_see("3r2k1/8/8/3p4/8/8/3R4/3R2K1 w - - 0 1", "d2d5", 100);
+    // The queen behind the bishop recaptures, a pawn and a knight for the bishop
+    assert_see("6k1/8/5n2/3p4/8/1B6/Q7/6K1 w - - 0 1", "b3d5", 90);
+    // Without it, the bishop is lost for a pawn
+    assert_see("6k1/8/5n2/3p4/8/1B6/8/6K1 w - - 0 1", "b3d5", -230);
+}
+
+#[test]
+fn see_of_promotions_and_en_passant() {
+    assert_see("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q", 800);
+    // The new queen is taken by the rook
+    assert_see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q", -100);
+    assert_see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q", 1300);
+    // A pawn recapturing on the last rank promotes as well
+    assert_see("1R6/P6k/2n5/8/8/8/8/4K3 b - - 0 1", "c6b8", -620);
+    assert_see("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 0);
+    assert_see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 100);
+}
+
+#[test]
+fn see_does_not_let_the_king_take_a_defended_piece() {
+    // After exd2, the king cannot take back as the bishop defends the pawn
+    assert_see("4k3/8/8/8/1b6/4p3/3p4/3RK3 w - - 0 1", "d1d2", -400);
+    // Without the bishop, it can
+    assert_see("4k3/8/8/8/8/4p3/3p4/3RK3 w - - 0 1", "d1d2", -300);
+}
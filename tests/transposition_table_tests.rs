@@ -0,0 +1,101 @@
+use zeno::transposition_table::{Bound, TranspositionTable};
+use zeno::utils::{Move, MoveType};
+
+fn e2e4() -> Move {
+    Move {
+        source: 12,
+        destination: 28,
+        move_type: MoveType::Normal,
+        move_score: 0,
+    }
+}
+
+#[test]
+fn transposition_table_size_is_a_power_of_two() {
+    for size_mb in [1, 3, 16, 100] {
+        let table = TranspositionTable::new(size_mb);
+        let bytes = table.capacity() * 16;
+        assert!(table.capacity().is_power_of_two());
+        assert!(bytes <= size_mb * 1024 * 1024);
+        assert!(bytes * 2 > size_mb * 1024 * 1024);
+    }
+}
+
+#[test]
+fn transposition_table_store_and_probe() {
+    let table = TranspositionTable::new(1);
+    let key = 0x1234_5678_9ABC_DEF0;
+    assert!(table.probe(key).is_none());
+
+    let promotion = Move {
+        source: 52,
+        destination: 60,
+        move_type: MoveType::PawnToQueen,
+        move_score: 0,
+    };
+    table.store(key, Some(promotion), -1234, 7, Bound::Lower);
+    let entry = table.probe(key).unwrap();
+    assert_eq!(
+        entry.best_move.map(|m| m.to_u16()),
+        Some(promotion.to_u16())
+    );
+    assert_eq!(entry.best_move.unwrap().move_type, MoveType::PawnToQueen);
+    assert_eq!(entry.score, -1234);
+    assert_eq!(entry.depth, 7);
+    assert_eq!(entry.bound, Bound::Lower);
+
+    // Same bucket, different position
+    assert!(table.probe(key ^ (1 << 63)).is_none());
+}
+
+#[test]
+fn transposition_table_keeps_the_best_move_when_none_is_given() {
+    let table = TranspositionTable::new(1);
+    table.store(42, Some(e2e4()), 10, 3, Bound::Exact);
+    table.store(42, None, -5, 4, Bound::Upper);
+    let entry = table.probe(42).unwrap();
+    assert_eq!(entry.best_move.map(|m| m.to_u16()), Some(e2e4().to_u16()));
+    assert_eq!(entry.score, -5);
+    assert_eq!(entry.bound, Bound::Upper);
+}
+
+#[test]
+fn transposition_table_replaces_shallow_and_old_entries_first() {
+    let table = TranspositionTable::new(1);
+    let buckets = (table.capacity() / 4) as u64;
+
+    // Five positions falling in the same bucket of four entries
+    let keys: Vec<u64> = (1..=5).map(|i| i * buckets).collect();
+    for (depth, key) in keys[..4].iter().enumerate() {
+        table.store(*key, None, 0, 3 + depth as u8, Bound::Exact);
+    }
+    table.store(keys[4], None, 0, 1, Bound::Exact);
+    assert!(table.probe(keys[0]).is_none());
+    assert!(table.probe(keys[4]).is_some());
+
+    // Deep entries from a previous search are replaced before a shallow one from the current search
+    table.new_search();
+    table.store(keys[4], None, 0, 1, Bound::Exact);
+    table.store(keys[0], None, 0, 2, Bound::Exact);
+    assert!(table.probe(keys[0]).is_some());
+    assert!(table.probe(keys[1]).is_none());
+    assert!(table.probe(keys[4]).is_some());
+}
+
+#[test]
+fn transposition_table_hashfull() {
+    let table = TranspositionTable::new(1);
+    assert_eq!(table.hashfull(), 0);
+    for key in 0..table.capacity() as u64 {
+        table.store(key, None, 0, 1, Bound::Exact);
+    }
+    assert!(table.hashfull() >= 990);
+
+    table.new_search();
+    assert_eq!(table.hashfull(), 0);
+
+    table.store(1, None, 0, 1, Bound::Exact);
+    table.clear();
+    assert_eq!(table.hashfull(), 0);
+    assert!(table.probe(1).is_none());
+}
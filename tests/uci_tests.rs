@@ -0,0 +1,219 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use zeno::position::Position;
+use zeno::uci::{STARTPOS, engine_options, move_to_uci, search_options, uci_move, uci_position};
+use zeno::utils::MoveType;
+
+fn position_after(command: &str) -> Position {
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    uci_position(command, &mut position);
+    position
+}
+
+#[test]
+fn uci_position_startpos() {
+    assert_eq!(
+        position_after("position startpos").key(),
+        Position::from_fen(STARTPOS).key()
+    );
+
+    let position = position_after("position startpos moves e2e4 c7c5 g1f3");
+    let expected =
+        Position::from_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+    assert_eq!(position.key(), expected.key());
+}
+
+#[test]
+fn uci_position_fen() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_eq!(
+        position_after(&format!("position fen {}", fen)).key(),
+        Position::from_fen(fen).key()
+    );
+
+    // Castling, then a double push allowing an en passant capture
+    let position = position_after(&format!("position fen {} moves e1g1 c7c5 d5c6", fen));
+    let expected =
+        Position::from_fen("r3k2r/p2pqpb1/bnP1pnp1/4N3/1p2P3/2N2Q1p/PPPBBPPP/R4RK1 b kq - 0 2");
+    assert_eq!(position.key(), expected.key());
+
+    // The move counters may be left out
+    let position = position_after("position fen 8/P6k/8/8/8/8/8/K7 w - - moves a7a8n");
+    assert_eq!(
+        position.key(),
+        Position::from_fen("N7/7k/8/8/8/8/8/K7 b - - 0 1").key()
+    );
+}
+
+#[test]
+fn uci_position_keeps_the_position_on_an_illegal_move() {
+    // None of the moves are played, not even the legal ones before the illegal one
+    let position = position_after("position startpos moves e2e4 e2e4 e7e5");
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    let position = position_after("position fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1 moves e1e2 e2e3");
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+}
+
+#[test]
+fn uci_position_keeps_the_position_on_an_invalid_fen() {
+    for command in [
+        "position fen",
+        "position fen 4k3/8/8/8/8/8/8/4K3 w - e3 moves e1e2",
+        "position fen 4k3/8/8/8/8/8/8/4KK2 w - - 0 1 moves e1e2",
+        "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+    ] {
+        let position = position_after(command);
+        assert_eq!(
+            position.to_fen(),
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "{}",
+            command
+        );
+    }
+}
+
+#[test]
+fn uci_move_notation() {
+    let position = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R b KQkq - 0 1");
+    for move_string in ["e8g8", "a8a1", "h8h1"] {
+        let mov = uci_move(move_string, &position).unwrap();
+        assert_eq!(move_to_uci(&mov, &position), move_string);
+    }
+    // Not legal, castling through the square attacked by the pawn, or not moves at all
+    for move_string in ["e1g1", "e8c8", "e8e7e", "b7b8q", "e8f9", ""] {
+        assert!(
+            uci_move(move_string, &position).is_none(),
+            "{}",
+            move_string
+        );
+    }
+
+    let position = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    for move_string in ["b7a8q", "b7b8r", "b7b8n", "e1c1"] {
+        let mov = uci_move(move_string, &position).unwrap();
+        assert_eq!(move_to_uci(&mov, &position), move_string);
+    }
+}
+
+#[test]
+fn uci_chess960_castling_notation() {
+    let fen = "1rk3r1/pppppppp/8/8/8/8/PPPPPPPP/1RK3R1 w GBgb - 0 1";
+    let mut position = Position::from_fen(fen);
+    position.set_chess960(true);
+    // The king takes its own rook, even when it does not move
+    for move_string in ["c1g1", "c1b1"] {
+        let mov = uci_move(move_string, &position).unwrap();
+        assert_eq!(move_to_uci(&mov, &position), move_string);
+    }
+    assert!(uci_move("c1c1", &position).is_none());
+
+    // The mode is kept by the following position commands
+    uci_position(
+        &format!("position fen {} moves c1b1 c8g8", fen),
+        &mut position,
+    );
+    assert!(position.is_chess960());
+    let expected = Position::from_fen("1r3rk1/pppppppp/8/8/8/8/PPPPPPPP/2KR2R1 w - - 2 2");
+    assert_eq!(position.key(), expected.key());
+
+    // Standard notation
+    let mut position = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let mov = uci_move("e1g1", &position).unwrap();
+    position.set_chess960(true);
+    assert_eq!(move_to_uci(&mov, &position), "e1h1");
+}
+
+#[test]
+fn uci_castling_next_to_a_king_step() {
+    // Without Chess960, the castling landing next to the king is told apart from the king step
+    for (fen, king_step, castle, castling_type) in [
+        (
+            "3k4/8/8/8/8/8/8/1R1K4 w B - 0 1",
+            "d1c1",
+            "d1b1",
+            MoveType::LongCastle,
+        ),
+        (
+            "3k4/8/8/8/8/8/8/5K1R w H - 0 1",
+            "f1g1",
+            "f1h1",
+            MoveType::ShortCastle,
+        ),
+    ] {
+        let position = Position::from_fen(fen);
+        assert!(!position.is_chess960());
+        let mov = uci_move(king_step, &position).unwrap();
+        assert_eq!(mov.move_type, MoveType::Normal);
+        assert_eq!(move_to_uci(&mov, &position), king_step);
+        let mov = uci_move(castle, &position).unwrap();
+        assert_eq!(mov.move_type, castling_type);
+        assert_eq!(move_to_uci(&mov, &position), castle);
+    }
+}
+
+#[test]
+fn uci_engine_options() {
+    let mut options = engine_options();
+    let lines: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+    assert!(lines.contains(&"option name Hash type spin default 16 min 1 max 65536".to_string()));
+    assert!(lines.contains(&"option name Clear Hash type button".to_string()));
+    assert!(lines.contains(&"option name UCI_Chess960 type check default false".to_string()));
+
+    let defaults = search_options(&options);
+    assert_eq!(
+        (defaults.threads, defaults.multi_pv, defaults.elo),
+        (1, 1, None)
+    );
+
+    options.set("threads", "8").unwrap();
+    options.set("MultiPV", "3").unwrap();
+    options.set("UCI_Elo", "1200").unwrap();
+    assert_eq!(search_options(&options).elo, None);
+    options.set("UCI_LimitStrength", "true").unwrap();
+    let search_options = search_options(&options);
+    assert_eq!(
+        (
+            search_options.threads,
+            search_options.multi_pv,
+            search_options.elo
+        ),
+        (8, 3, Some(1200))
+    );
+}
+
+#[test]
+fn uci_unknown_commands_do_not_stop_the_search() {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_zeno"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = engine.stdin.take().unwrap();
+    let mut output = BufReader::new(engine.stdout.take().unwrap()).lines();
+    let mut read_until = |expected: &str| -> Vec<String> {
+        let mut lines = vec![];
+        for line in output.by_ref() {
+            let line = line.unwrap();
+            let done = line.starts_with(expected);
+            lines.push(line);
+            if done {
+                break;
+            }
+        }
+        lines
+    };
+
+    writeln!(input, "position startpos\ngo infinite\nxyzzy\nisready").unwrap();
+    let lines = read_until("readyok");
+    assert!(lines.iter().all(|line| !line.starts_with("bestmove")));
+
+    writeln!(input, "stop").unwrap();
+    assert!(
+        read_until("bestmove")
+            .last()
+            .unwrap()
+            .starts_with("bestmove")
+    );
+    writeln!(input, "quit").unwrap();
+    assert!(engine.wait().unwrap().success());
+}
@@ -0,0 +1,113 @@
+use zeno::move_list::MoveList;
+use zeno::moves_generator::generate_pseudo_legal_moves;
+use zeno::position::Position;
+use zeno::utils::Move;
+use zeno::zobrist_hash::hash_position;
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn find_move(position: &Position, source: i8, destination: i8) -> Move {
+    let mut moves = MoveList::new();
+    generate_pseudo_legal_moves(position, &position.get_turn(), &mut moves);
+    moves
+        .into_iter()
+        .find(|m| m.source == source && m.destination == destination)
+        .unwrap()
+}
+
+fn play(position: &mut Position, moves: &[(i8, i8)]) {
+    for (source, destination) in moves {
+        let mov = find_move(position, *source, *destination);
+        position.make_move(&mov, true);
+    }
+}
+
+fn assert_incremental_key(depth: i32, position: &mut Position) {
+    assert_eq!(position.key(), hash_position(position));
+    if depth == 0 {
+        return;
+    }
+    let turn = position.get_turn();
+    let mut moves = MoveList::new();
+    generate_pseudo_legal_moves(position, &turn, &mut moves);
+    for mov in moves {
+        let undo_move = position.make_move(&mov, true);
+        if !position.is_check(&turn) {
+            assert_incremental_key(depth - 1, position);
+        }
+        position.unmake_move(&undo_move);
+    }
+}
+
+#[test]
+fn zobrist_keys_are_reproducible() {
+    assert_eq!(
+        Position::from_fen(STARTPOS).key(),
+        Position::from_fen(STARTPOS).key()
+    );
+    assert_ne!(
+        Position::from_fen(STARTPOS).key(),
+        Position::from_fen(KIWIPETE).key()
+    );
+}
+
+#[test]
+fn zobrist_incremental_key_matches_recomputation() {
+    assert_incremental_key(3, &mut Position::from_fen(STARTPOS));
+    assert_incremental_key(3, &mut Position::from_fen(KIWIPETE));
+}
+
+#[test]
+fn zobrist_transpositions_share_a_key() {
+    // 1. Nf3 Nf6 2. Nc3 Nc6 and 1. Nc3 Nc6 2. Nf3 Nf6
+    let mut first = Position::from_fen(STARTPOS);
+    play(&mut first, &[(6, 21), (62, 45), (1, 18), (57, 42)]);
+    let mut second = Position::from_fen(STARTPOS);
+    play(&mut second, &[(1, 18), (57, 42), (6, 21), (62, 45)]);
+    assert_eq!(first.key(), second.key());
+}
+
+#[test]
+fn zobrist_key_depends_on_side_castling_and_en_passant() {
+    let key = Position::from_fen(STARTPOS).key();
+    let black_to_move = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
+    let no_castling = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1";
+    assert_ne!(key, Position::from_fen(black_to_move).key());
+    assert_ne!(key, Position::from_fen(no_castling).key());
+
+    let with_en_passant = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+    let without_en_passant = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3";
+    assert_ne!(
+        Position::from_fen(with_en_passant).key(),
+        Position::from_fen(without_en_passant).key()
+    );
+}
+
+#[test]
+fn zobrist_key_ignores_en_passant_squares_without_attacking_pawns() {
+    // No white pawn can take on e6
+    let with_en_passant = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+    let without_en_passant = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+    assert_eq!(
+        Position::from_fen(with_en_passant).key(),
+        Position::from_fen(without_en_passant).key()
+    );
+
+    // The same after a double push, only the pawn next to the pushed one makes a difference
+    let mut position = Position::from_fen(STARTPOS);
+    play(&mut position, &[(12, 28)]);
+    let expected = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+    assert_eq!(position.key(), Position::from_fen(expected).key());
+
+    let mut position = Position::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1");
+    play(&mut position, &[(12, 28)]);
+    assert_ne!(
+        position.key(),
+        Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").key()
+    );
+    assert_eq!(
+        position.key(),
+        Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").key()
+    );
+}